@@ -0,0 +1,71 @@
+//! Load 20k package-like rows, `cargo bench -p orm --bench bulk_insert`
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use orm::{db_init, DbHandler, TableOptions};
+
+const ROWS: usize = 20_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Package {
+	repo: String,
+	name: String,
+	version: String,
+	desc: Option<String>,
+	depends: Vec<String>,
+	isize: i64,
+}
+
+impl TableOptions for Package {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["repo", "name"]]
+	}
+
+	fn search() -> &'static [(&'static str, f64)] {
+		&[("name", 10.0), ("desc", 1.0)]
+	}
+}
+
+fn packages() -> Vec<Package> {
+	let repos = ["core", "extra", "community"];
+	(0..ROWS)
+		.map(|i| Package {
+			repo: repos[i % repos.len()].to_string(),
+			name: format!("package-{i}"),
+			version: format!("{}.{}-1", i % 7, i % 13),
+			desc: Some(format!("description of package {i}")),
+			depends: vec![String::from("glibc"), format!("package-{}", i / 2)],
+			isize: i as i64 * 1024,
+		})
+		.collect()
+}
+
+/// Best of `runs`, every run starts with an empty in-memory database
+fn measure(runs: usize, load: impl Fn(&DbHandler, Vec<Package>)) -> Duration {
+	(0..runs)
+		.map(|_| {
+			let db = db_init(None).unwrap();
+			let rows = packages();
+			let start = Instant::now();
+			load(&db, rows);
+			let elapsed = start.elapsed();
+			assert_eq!(db.get_repository::<Package>().unwrap().count(json!({})).unwrap(), ROWS as u64);
+			elapsed
+		})
+		.min()
+		.unwrap()
+}
+
+fn main() {
+	let insert_all = measure(5, |db, rows| {
+		db.get_repository::<Package>().unwrap().insert_all(rows).unwrap();
+	});
+	let add_all = measure(5, |db, rows| {
+		db.get_repository::<Package>().unwrap().add_all(rows).unwrap();
+	});
+	println!("insert_all  {ROWS} rows  {insert_all:?}");
+	println!("add_all     {ROWS} rows  {add_all:?}");
+}
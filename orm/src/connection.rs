@@ -0,0 +1,362 @@
+use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+use rusqlite::{Connection, Params};
+use rusqlite::types::Value as SqlValue;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_value, Map, Value};
+use tracing::{debug, info};
+
+use crate::error::{DbError, DbResult};
+use crate::table::{Table, TableOptions};
+use crate::filter::FilterCompiler;
+use crate::join::Join;
+use crate::migration;
+use crate::query::Query;
+use crate::util::{from_row, from_rows, named_params, quote, value_to_sql};
+
+/// Open database at `path` or in-memory database when `path` is [None]
+pub fn db_init(path: Option<&Path>) -> DbResult<DbHandler> {
+	let connection = match path {
+		Some(path) => {
+			info!("Opening database `{}`", path.display());
+			if let Some(parent) = path.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			Connection::open(path)?
+		}
+		None => {
+			info!("Creating in-memory database");
+			Connection::open_in_memory()?
+		}
+	};
+	crate::filter::register(&connection)?;
+	debug!("Create database successfully!");
+	Ok(DbHandler { connection })
+}
+
+pub struct DbHandler {
+	connection: Connection,
+}
+
+impl Deref for DbHandler {
+	type Target = Connection;
+
+	fn deref(&self) -> &Self::Target {
+		&self.connection
+	}
+}
+
+impl DerefMut for DbHandler {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.connection
+	}
+}
+
+impl DbHandler {
+	pub fn query<T: DeserializeOwned>(&self, sql: &str, param: impl Params) -> DbResult<T> {
+		debug!("{}", sql);
+		let row = self.query_row(sql, param, from_row)?;
+		Ok(from_value(row)?)
+	}
+
+	pub fn query_all<T: DeserializeOwned>(&self, sql: &str, param: impl Params) -> DbResult<Vec<T>> {
+		debug!("{}", sql);
+		let mut stmt = self.prepare(sql)?;
+		let rows = stmt.query(param)?;
+		Ok(from_value::<Vec<T>>(from_rows(rows)?)?)
+	}
+
+	/// Repository of `S`, table is created if not exists
+	pub fn get_repository<S: Serialize + DeserializeOwned + TableOptions>(&self) -> DbResult<Repository<'_, S>> {
+		let table = Table::new()?;
+		Repository { connection: self, table }.init()
+	}
+
+	/// Join table of `L` with table of `R` on column `on` existing in both, see [Join]
+	pub fn join<L, R>(&self, on: &str) -> DbResult<Join<'_, L, R>>
+	where
+		L: Serialize + DeserializeOwned + TableOptions,
+		R: Serialize + DeserializeOwned + TableOptions,
+	{
+		Join::new(self.get_repository()?, self.get_repository()?, on)
+	}
+}
+
+/// Row found by [Repository::search]
+#[derive(Debug)]
+pub struct SearchHit<T> {
+	pub row: T,
+	/// `bm25` of the row, lower is better
+	pub rank: f64,
+	/// part of the best matching column, matched terms are enclosed in `[` and `]`
+	pub snippet: String,
+}
+
+pub struct Repository<'a, T: Serialize + DeserializeOwned> {
+	connection: &'a DbHandler,
+	table: Table<T>,
+}
+
+impl<'a, T: Serialize + DeserializeOwned> Repository<'a, T> {
+	fn init(self) -> DbResult<Self> {
+		self.create()?;
+		Ok(self)
+	}
+
+	/// Create table, indexes and full-text index if not exists,
+	/// existing table is migrated first if its recorded fingerprint doesn't match
+	fn create(&self) -> DbResult<()> {
+		let fingerprint = self.table.fingerprint();
+		let recorded = migration::recorded(self.connection, &self.table.name)?;
+		let changed = recorded.as_ref() != Some(&fingerprint);
+		if changed {
+			if let Some(diff) = migration::diff(self.connection, &self.table)? {
+				let reason = if recorded.is_some() { diff.to_string() } else { String::from("no recorded fingerprint") };
+				info!("Migrating table {} ({:?}): {}", self.table.name, self.table.migration, reason);
+				self.transaction(|repo| migration::migrate(repo.connection, &repo.table, &diff))?;
+			}
+		}
+		self.execute(&self.table.create_table_script(), &[])?;
+		for index in self.table.create_index_scripts() {
+			self.execute(&index, &[])?;
+		}
+		let scripts = self.table.create_fts_scripts();
+		if !scripts.is_empty() {
+			let fts = self.table.fts_name();
+			let exists: bool = self.connection.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = ?)", [&fts], |row| row.get(0))?;
+			for script in scripts {
+				self.execute(&script, &[])?;
+			}
+			// index rows inserted before the index was added
+			if !exists {
+				let fts = quote(&fts);
+				self.execute(&format!("INSERT INTO {fts} ({fts}) VALUES ('rebuild')"), &[])?;
+			}
+		}
+		if changed {
+			migration::record(self.connection, &self.table.name, &fingerprint)?;
+		}
+		Ok(())
+	}
+
+	pub fn columns(&self) -> Vec<&str> {
+		self.table.fields.iter().map(|it| it.name.as_str()).collect()
+	}
+
+	pub fn take_all(&self) -> DbResult<Vec<T>> {
+		let res = self.all()?;
+		self.clear()?;
+		Ok(res)
+	}
+
+	pub fn clear(&self) -> DbResult<()> {
+		self.execute(&format!("DELETE FROM {} WHERE 1=1", quote(&self.table.name)), &[])?;
+		Ok(())
+	}
+
+	/// Drop and create the table again, for table created before schema of `T` changed
+	pub fn reset(&self) -> DbResult<()> {
+		for script in self.table.drop_fts_scripts() {
+			self.execute(&script, &[])?;
+		}
+		self.execute(&format!("DROP TABLE IF EXISTS {}", quote(&self.table.name)), &[])?;
+		self.create()
+	}
+
+	pub fn all(&self) -> DbResult<Vec<T>> {
+		self.query_all(&format!("SELECT {} FROM {}", self.table.select_columns(), quote(&self.table.name)), &[])
+	}
+
+	/// Insert `obj`, existing row with the same primary key or unique key is replaced
+	pub fn add(&self, obj: T) -> DbResult<T> {
+		let params = self.table.to_params(&obj)?;
+		let sql = self.table.insert_script(true);
+		debug!("{}", sql);
+		let mut stmt = self.connection.prepare_cached(&sql)?;
+		let row = stmt.query_row(named_params(&params).as_slice(), |row| self.table.read_row(row))?;
+		Ok(from_value(row)?)
+	}
+
+	/// [Repository::add] every object in a single transaction
+	pub fn add_all(&self, objs: Vec<T>) -> DbResult<Vec<T>> {
+		self.transaction(|repo| objs.into_iter().map(|it| repo.add(it)).collect())
+	}
+
+	/// Like [Repository::add_all] without reading rows back, returns number of inserted rows
+	pub fn insert_all(&self, objs: impl IntoIterator<Item=T>) -> DbResult<usize> {
+		self.transaction(|repo| {
+			let sql = repo.table.insert_script(false);
+			debug!("{}", sql);
+			let mut stmt = repo.connection.prepare_cached(&sql)?;
+			let mut count = 0;
+			for obj in objs {
+				count += stmt.execute(named_params(&repo.table.to_params(&obj)?).as_slice())?;
+			}
+			Ok(count)
+		})
+	}
+
+	/// Run `f` inside a savepoint, changes are rolled back when `f` returns error.
+	/// Can be nested and span other repositories of the same connection.
+	pub fn transaction<R, E: From<DbError>>(&self, f: impl FnOnce(&Self) -> Result<R, E>) -> Result<R, E> {
+		self.connection.execute_batch("SAVEPOINT repository").map_err(DbError::from)?;
+		match f(self) {
+			Ok(res) => {
+				self.connection.execute_batch("RELEASE repository").map_err(DbError::from)?;
+				Ok(res)
+			}
+			Err(err) => {
+				self.connection.execute_batch("ROLLBACK TO repository; RELEASE repository").map_err(DbError::from)?;
+				Err(err)
+			}
+		}
+	}
+
+	/// Query builder with ordering, pagination and projection
+	pub fn query(&self) -> Query<'_, 'a, T> {
+		Query::new(self)
+	}
+
+	/// Rows matched by `filter`, see [Query::filter]
+	pub fn find(&self, filter: Value) -> DbResult<Vec<T>> {
+		self.query().filter(filter).all()
+	}
+
+	/// First row matched by `filter`
+	pub fn find_one(&self, filter: Value) -> DbResult<Option<T>> {
+		self.query().filter(filter).one()
+	}
+
+	/// Number of rows matched by `filter`
+	pub fn count(&self, filter: Value) -> DbResult<u64> {
+		let (f, params) = self.compile_filter(filter)?;
+		let sql = format!("SELECT count(*) FROM {} WHERE {}", quote(&self.table.name), f);
+		debug!("{}", sql);
+		Ok(self.connection.query_row(&sql, named_params(&params).as_slice(), |row| row.get(0))?)
+	}
+
+	pub fn exists(&self, filter: Value) -> DbResult<bool> {
+		let (f, params) = self.compile_filter(filter)?;
+		let sql = format!("SELECT EXISTS (SELECT 1 FROM {} WHERE {})", quote(&self.table.name), f);
+		debug!("{}", sql);
+		Ok(self.connection.query_row(&sql, named_params(&params).as_slice(), |row| row.get(0))?)
+	}
+
+	/// Rows matching full-text `query` (fts5 syntax) and `filter`, the best match comes first.
+	/// Fails if the table has no [TableOptions::search] column.
+	pub fn search(&self, query: &str, filter: Value, limit: Option<usize>) -> DbResult<Vec<SearchHit<T>>> {
+		if self.table.search.is_empty() {
+			return Err(DbError::Schema(format!("Table {} has no full-text index", self.table.name)));
+		}
+		let (f, mut params) = self.compile_filter(filter)?;
+		params.push((String::from(":query"), SqlValue::from(query.to_string())));
+		let name = quote(&self.table.name);
+		let fts = quote(&self.table.fts_name());
+		let rowid = if self.table.rowid { format!("{name}.rowid, ") } else { String::new() };
+		// filter is applied outside of the subquery, so its columns aren't ambiguous with columns of fts table
+		let mut sql = format!(
+			"SELECT {rowid}{name}.*, _rank, _snippet FROM {name} JOIN \
+			(SELECT rowid AS _rowid, {} AS _rank, snippet({fts}, -1, '[', ']', '…', 12) AS _snippet FROM {fts} WHERE {fts} MATCH :query) \
+			ON {name}.rowid = _rowid WHERE {f} ORDER BY _rank",
+			self.table.rank_expr()
+		);
+		if let Some(limit) = limit {
+			sql.push_str(&format!(" LIMIT {limit}"));
+		}
+		debug!("{}", sql);
+		let mut stmt = self.connection.prepare_cached(&sql)?;
+		let mut rows = stmt.query(named_params(&params).as_slice())?;
+		let mut res = Vec::new();
+		while let Some(row) = rows.next()? {
+			let mut value = self.table.read_row(row)?;
+			let (rank, snippet) = match &mut value {
+				Value::Object(map) => (map.remove("_rank"), map.remove("_snippet")),
+				_ => (None, None),
+			};
+			res.push(SearchHit {
+				row: from_value(value)?,
+				rank: rank.and_then(|it| it.as_f64()).unwrap_or_default(),
+				snippet: snippet.and_then(|it| it.as_str().map(String::from)).unwrap_or_default(),
+			});
+		}
+		Ok(res)
+	}
+
+	/// Set columns in `patch` of every row matched by `filter`, returns number of updated rows
+	pub fn update(&self, filter: Value, patch: Value) -> DbResult<usize> {
+		let (f, mut params) = self.compile_filter(filter)?;
+		let Value::Object(patch) = patch else {
+			return Err(DbError::TypeMismatch { key: String::from("patch"), expected: "object", found: patch });
+		};
+		let mut set = Vec::new();
+		for (field, value) in patch {
+			let Some(column) = self.table.fields.iter().find(|it| it.name == field) else {
+				return Err(DbError::UnknownField { table: self.table.name.clone(), field });
+			};
+			// prefixed to not clash with parameters of filter
+			let param_name = format!(":set{}", set.len());
+			set.push(format!("{} = {param_name}", quote(&field)));
+			let value = if column.typ.is_json() && !value.is_null() {
+				SqlValue::from(value.to_string())
+			} else {
+				value_to_sql(value)
+			};
+			params.push((param_name, value));
+		}
+		if set.is_empty() {
+			return Ok(0);
+		}
+		let sql = format!("UPDATE {} SET {} WHERE {}", quote(&self.table.name), set.join(", "), f);
+		self.execute(&sql, &params)
+	}
+
+	/// Delete every row matched by `filter`, returns number of deleted rows
+	pub fn delete(&self, filter: Value) -> DbResult<usize> {
+		let (f, params) = self.compile_filter(filter)?;
+		let sql = format!("DELETE FROM {} WHERE {}", quote(&self.table.name), f);
+		self.execute(&sql, &params)
+	}
+
+	pub(crate) fn table(&self) -> &Table<T> {
+		&self.table
+	}
+
+	/// `WHERE` clause of json filter and its named parameters
+	pub(crate) fn compile_filter(&self, filter: Value) -> DbResult<(String, Vec<(String, SqlValue)>)> {
+		FilterCompiler::compile(&self.table.name, &self.table.fields, filter)
+	}
+
+	/// [Repository::compile_filter] for table referred as `alias`
+	pub(crate) fn compile_filter_aliased(&self, alias: &str, filter: Value) -> DbResult<(String, Vec<(String, SqlValue)>)> {
+		FilterCompiler::compile_aliased(alias, &self.table.name, &self.table.fields, filter)
+	}
+
+	pub(crate) fn connection(&self) -> &DbHandler {
+		self.connection
+	}
+
+	fn execute(&self, sql: &str, params: &[(String, SqlValue)]) -> DbResult<usize> {
+		debug!("{}", sql);
+		Ok(self.connection.execute(sql, named_params(params).as_slice())?)
+	}
+
+	fn query_all(&self, sql: &str, params: &[(String, SqlValue)]) -> DbResult<Vec<T>> {
+		self.query_values(sql, params)?.into_iter().map(|it| Ok(from_value(Value::Object(it))?)).collect()
+	}
+
+	pub(crate) fn query_values(&self, sql: &str, params: &[(String, SqlValue)]) -> DbResult<Vec<Map<String, Value>>> {
+		debug!("{}", sql);
+		let mut stmt = self.connection.prepare_cached(sql)?;
+		let mut rows = stmt.query(named_params(params).as_slice())?;
+		let mut res = Vec::new();
+		while let Some(row) = rows.next()? {
+			if let Value::Object(map) = self.table.read_row(row)? {
+				res.push(map);
+			}
+		}
+		Ok(res)
+	}
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use serde_json::Value;
+
+use crate::ser::SerError;
+
+/// Error of [crate::Repository] and filters
+#[derive(Debug)]
+pub enum DbError {
+	/// filter or patch refers to a column that doesn't exist, `field` is a dotted path for nested fields
+	UnknownField { table: String, field: String },
+	/// operator with `$` prefix that isn't supported
+	InvalidOperator(String),
+	/// operand can't be used with the operator, e.g. `{"$regex": 1}`
+	TypeMismatch { key: String, expected: &'static str, found: Value },
+	Sqlite(rusqlite::Error),
+	/// struct can't be stored as table or row doesn't fit the struct
+	Schema(String),
+	Io(io::Error),
+}
+
+impl Display for DbError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DbError::UnknownField { table, field } => write!(f, "Unknown field `{field}` of table {table}"),
+			DbError::InvalidOperator(op) => write!(f, "Invalid operator `{op}`"),
+			DbError::TypeMismatch { key, expected, found } => write!(f, "`{key}` expects {expected}, found `{found}`"),
+			DbError::Sqlite(err) => write!(f, "Sqlite error: {err}"),
+			DbError::Schema(msg) => f.write_str(msg),
+			DbError::Io(err) => write!(f, "Can't open database: {err}"),
+		}
+	}
+}
+
+impl Error for DbError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			DbError::Sqlite(err) => Some(err),
+			DbError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<rusqlite::Error> for DbError {
+	fn from(err: rusqlite::Error) -> Self {
+		DbError::Sqlite(err)
+	}
+}
+
+impl From<io::Error> for DbError {
+	fn from(err: io::Error) -> Self {
+		DbError::Io(err)
+	}
+}
+
+impl From<SerError> for DbError {
+	fn from(err: SerError) -> Self {
+		DbError::Schema(err.0)
+	}
+}
+
+impl From<serde_json::Error> for DbError {
+	fn from(err: serde_json::Error) -> Self {
+		DbError::Schema(err.to_string())
+	}
+}
+
+pub type DbResult<T> = Result<T, DbError>;
@@ -0,0 +1,117 @@
+#[derive(Debug)]
+pub struct Field {
+	pub name: String,
+	pub typ: FieldType,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldType {
+	Char,
+	String,
+	Bool,
+	U8,
+	U16,
+	U32,
+	U64,
+	I8,
+	I16,
+	I32,
+	I64,
+	F32,
+	F64,
+	Bytes,
+	/// String column compared using collation declared by [crate::TableOptions::collations]
+	/// (collation must be registered to the connection before creating the table)
+	Collated(&'static str),
+	/// `Vec<T>`, map, nested struct or enum serialized with serde_json
+	Json,
+	/// `Option<T>`, column accepts NULL
+	Nullable(Box<FieldType>),
+	/// Untyped field like [serde_json::Value], column is declared without type and stores any value as is
+	Any,
+}
+
+impl FieldType {
+	pub fn sqlite_type(&self) -> &'static str {
+		match self {
+			FieldType::Char => {
+				"CHARACTER(1)"
+			}
+			FieldType::String | FieldType::Collated(_) => {
+				"TEXT"
+			}
+			FieldType::Bool => {
+				"TINYINT"
+			}
+			FieldType::U8 | FieldType::I8 => {
+				"TINYINT"
+			}
+			FieldType::U16 | FieldType::I16 => {
+				"SMALLINT"
+			}
+			FieldType::U32 | FieldType::I32 => {
+				"INTEGER"
+			}
+			FieldType::U64 | FieldType::I64 => {
+				"BIGINT"
+			}
+			FieldType::F32 => {
+				"FLOAT"
+			}
+			FieldType::F64 => {
+				"DOUBLE"
+			}
+			FieldType::Bytes => {
+				"BLOB"
+			}
+			FieldType::Json => {
+				"JSON"
+			}
+			FieldType::Nullable(inner) => {
+				inner.sqlite_type()
+			}
+			FieldType::Any => {
+				""
+			}
+		}
+	}
+
+	pub fn collation(&self) -> Option<&'static str> {
+		match self {
+			FieldType::Collated(name) => Some(name),
+			FieldType::Nullable(inner) => inner.collation(),
+			_ => None
+		}
+	}
+
+	/// Stored as integer, has to be converted back to json boolean when reading
+	pub fn is_bool(&self) -> bool {
+		match self {
+			FieldType::Bool => true,
+			FieldType::Nullable(inner) => inner.is_bool(),
+			_ => false
+		}
+	}
+
+	pub fn is_json(&self) -> bool {
+		match self {
+			FieldType::Json => true,
+			FieldType::Nullable(inner) => inner.is_json(),
+			_ => false
+		}
+	}
+
+	/// Text column with `collation`, [None] if the column isn't text
+	pub fn collated(&self, collation: &'static str) -> Option<FieldType> {
+		match self {
+			FieldType::String | FieldType::Collated(_) => Some(FieldType::Collated(collation)),
+			FieldType::Nullable(inner) => inner.collated(collation).map(|it| FieldType::Nullable(Box::new(it))),
+			_ => None
+		}
+	}
+
+	/// NULL is a valid value, e.g. column added to existing table can be left empty
+	pub fn is_nullable(&self) -> bool {
+		matches!(self, FieldType::Nullable(_) | FieldType::Any)
+	}
+}
@@ -0,0 +1,288 @@
+use std::error::Error;
+
+use rusqlite::Connection;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use serde_json::{Map, Value};
+
+use crate::error::{DbError, DbResult};
+use crate::field::Field;
+use crate::util::{quote, value_to_sql};
+
+/// Register functions used by compiled filters, `wildmatch(pattern, text)` and `regexp(pattern, text)`
+/// (`text REGEXP pattern`), both are false when `text` isn't TEXT.
+pub fn register(connection: &Connection) -> rusqlite::Result<()> {
+	let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+	connection.create_scalar_function("wildmatch", 2, flags, |ctx| {
+		Ok(match (ctx.get_raw(0), ctx.get_raw(1)) {
+			(ValueRef::Text(pattern), ValueRef::Text(text)) => {
+				wildmatch::WildMatch::new(&String::from_utf8_lossy(pattern)).matches(&String::from_utf8_lossy(text))
+			}
+			_ => false,
+		})
+	})?;
+	connection.create_scalar_function("regexp", 2, flags, |ctx| {
+		// pattern is compiled once per statement
+		let regex = ctx.get_or_create_aux(0, |it| -> Result<_, Box<dyn Error + Send + Sync>> {
+			Ok(regex_lite::Regex::new(it.as_str()?)?)
+		})?;
+		Ok(match ctx.get_raw(1) {
+			ValueRef::Text(text) => regex.is_match(&String::from_utf8_lossy(text)),
+			_ => false,
+		})
+	})?;
+	Ok(())
+}
+
+/// Expression of a value in the row
+struct Column {
+	expr: String,
+	/// json text, scalar operators match any element of array
+	json: bool,
+	/// dotted path of the field for errors
+	path: String,
+}
+
+/// Compile json filter into `WHERE` clause, result is the same as [crate::obj_match] against every row:
+///
+/// * `null` matches null field
+/// * string with `*` or `?` is a wildcard pattern, other scalars are compared for equality
+/// * array matches if any of its elements matches
+/// * object is a list of operators (`$eq`, `$ne`, `$lt`, `$lte`, `$gt`, `$gte`, `$in`, `$nin`, `$contains`,
+///   `$like`, `$glob`, `$regex`, `$exists`) and fields of nested object, all of them must match
+/// * `$and`, `$or`, `$nor` and `$not` combine filters of the row or patterns of a field
+/// * scalar operators match any element when the value is an array
+///
+/// Unlike [crate::obj_match], unknown fields, unknown operators and operands of the wrong type are errors.
+/// Nested fields of json columns can't be checked, missing ones are null.
+/// Only difference is ordering of column with collation, like version newtypes, which uses the collation.
+pub struct FilterCompiler<'a> {
+	table: &'a str,
+	fields: &'a [Field],
+	/// table alias qualifying columns, also prefix of parameters so filters of joined tables don't clash
+	alias: Option<&'a str>,
+	params: Vec<(String, SqlValue)>,
+}
+
+impl<'a> FilterCompiler<'a> {
+	pub fn compile(table: &'a str, fields: &'a [Field], filter: Value) -> DbResult<(String, Vec<(String, SqlValue)>)> {
+		let mut compiler = Self { table, fields, alias: None, params: Vec::new() };
+		let sql = compiler.document(filter)?;
+		Ok((sql, compiler.params))
+	}
+
+	/// [FilterCompiler::compile] for table referred as `alias`, e.g. `l` of `FROM Packages AS l`
+	pub fn compile_aliased(alias: &'a str, table: &'a str, fields: &'a [Field], filter: Value) -> DbResult<(String, Vec<(String, SqlValue)>)> {
+		let mut compiler = Self { table, fields, alias: Some(alias), params: Vec::new() };
+		let sql = compiler.document(filter)?;
+		Ok((sql, compiler.params))
+	}
+
+	fn param(&mut self, value: SqlValue) -> String {
+		let name = format!(":{}{}", self.alias.unwrap_or("p"), self.params.len());
+		self.params.push((name.clone(), value));
+		name
+	}
+
+	/// Filter of the whole row
+	fn document(&mut self, filter: Value) -> DbResult<String> {
+		match filter {
+			Value::Object(map) => {
+				let sql = map.into_iter()
+					.map(|(key, pattern)| match key.strip_prefix('$') {
+						Some(op @ ("and" | "or" | "nor" | "not")) => self.logical(op, pattern, Self::document),
+						Some(_) => Err(DbError::InvalidOperator(key)),
+						None => {
+							let Some(field) = self.fields.iter().find(|it| it.name == key) else {
+								return Err(DbError::UnknownField { table: self.table.to_string(), field: key });
+							};
+							let expr = match self.alias {
+								Some(alias) => format!("{alias}.{}", quote(&key)),
+								None => quote(&key),
+							};
+							let column = Column { json: field.typ.is_json(), path: key, expr };
+							self.pattern(&column, pattern)
+						}
+					})
+					.collect::<DbResult<Vec<_>>>()?;
+				Ok(all(sql))
+			}
+			Value::Array(arr) => {
+				let sql = arr.into_iter().map(|it| self.document(it)).collect::<DbResult<_>>()?;
+				Ok(any(sql))
+			}
+			filter => Err(DbError::TypeMismatch { key: String::from("filter"), expected: "object or array", found: filter }),
+		}
+	}
+
+	fn pattern(&mut self, column: &Column, pattern: Value) -> DbResult<String> {
+		match pattern {
+			Value::Null => Ok(is_null(column)),
+			Value::Array(arr) => {
+				let sql = arr.into_iter().map(|it| self.pattern(column, it)).collect::<DbResult<_>>()?;
+				Ok(any(sql))
+			}
+			Value::Object(map) => self.object(column, map),
+			Value::String(s) if s.contains(['*', '?']) => {
+				let p = self.param(SqlValue::from(s));
+				Ok(scalar(column, |v| format!("wildmatch({p}, {v})")))
+			}
+			value => self.eq(column, "$eq", value),
+		}
+	}
+
+	fn object(&mut self, column: &Column, map: Map<String, Value>) -> DbResult<String> {
+		let sql = map.into_iter()
+			.map(|(key, value)| {
+				if key.starts_with('$') {
+					return self.operator(column, &key, value);
+				}
+				let path = format!("{}.{}", column.path, key);
+				if !column.json {
+					return Err(DbError::UnknownField { table: self.table.to_string(), field: path });
+				}
+				// `->` keeps value as json text, NULL when missing
+				let nested = Column { expr: format!("({} -> {})", column.expr, quote_path(&key)), json: true, path };
+				self.pattern(&nested, value)
+			})
+			.collect::<DbResult<_>>()?;
+		Ok(all(sql))
+	}
+
+	/// `op` includes `$` prefix
+	fn operator(&mut self, column: &Column, op: &str, value: Value) -> DbResult<String> {
+		let mismatch = |expected, found| DbError::TypeMismatch { key: op.to_string(), expected, found };
+		Ok(match op {
+			"$and" | "$or" | "$nor" | "$not" => self.logical(&op[1..], value, |this, it| this.pattern(column, it))?,
+			"$eq" | "$contains" => self.eq(column, op, value)?,
+			"$ne" => format!("NOT {}", self.eq(column, op, value)?),
+			"$lt" | "$lte" | "$gt" | "$gte" => {
+				if !is_scalar(&value) {
+					return Err(mismatch("string, number or bool", value));
+				}
+				let cmp = match op {
+					"$lt" => "<",
+					"$lte" => "<=",
+					"$gt" => ">",
+					_ => ">=",
+				};
+				self.compare(column, cmp, value)
+			}
+			"$in" => self.include(column, op, value)?,
+			"$nin" => format!("NOT {}", self.include(column, op, value)?),
+			"$like" | "$glob" | "$regex" => {
+				let Value::String(pattern) = value else {
+					return Err(mismatch("string", value));
+				};
+				if op == "$regex" && regex_lite::Regex::new(&pattern).is_err() {
+					return Err(mismatch("valid regex", Value::String(pattern)));
+				}
+				let p = self.param(SqlValue::from(pattern));
+				match op {
+					"$like" => scalar(column, |v| format!("(typeof({v}) = 'text' AND {v} LIKE {p})")),
+					"$glob" => scalar(column, |v| format!("wildmatch({p}, {v})")),
+					_ => scalar(column, |v| format!("regexp({p}, {v})")),
+				}
+			}
+			"$exists" => match value {
+				Value::Bool(true) => format!("NOT {}", is_null(column)),
+				Value::Bool(false) => is_null(column),
+				value => return Err(mismatch("bool", value)),
+			},
+			op => return Err(DbError::InvalidOperator(op.to_string())),
+		})
+	}
+
+	/// `$and`, `$or` and `$nor` of a list (single value is a list of itself) or `$not` of `value`,
+	/// each one is compiled with `filter`
+	fn logical(&mut self, op: &str, value: Value, mut filter: impl FnMut(&mut Self, Value) -> DbResult<String>) -> DbResult<String> {
+		if op == "not" {
+			return Ok(format!("NOT {}", filter(self, value)?));
+		}
+		let values = match value {
+			Value::Array(arr) => arr,
+			value => vec![value],
+		};
+		let sql = values.into_iter().map(|it| filter(self, it)).collect::<DbResult<_>>()?;
+		Ok(match op {
+			"and" => all(sql),
+			"or" => any(sql),
+			_ => format!("NOT {}", any(sql)),
+		})
+	}
+
+	/// Equality without wildcard, `op` is used for errors
+	fn eq(&mut self, column: &Column, op: &str, value: Value) -> DbResult<String> {
+		match value {
+			Value::Null => Ok(is_null(column)),
+			Value::Array(_) | Value::Object(_) => {
+				Err(DbError::TypeMismatch { key: op.to_string(), expected: "string, number, bool or null", found: value })
+			}
+			value => Ok(self.compare(column, "=", value)),
+		}
+	}
+
+	/// Compare with scalar `value` of the same type, string is never compared with number
+	/// even if column has affinity
+	fn compare(&mut self, column: &Column, cmp: &str, value: Value) -> String {
+		let typ = if value.is_string() { "= 'text'" } else { "IN ('integer', 'real')" };
+		let p = self.param(value_to_sql(value));
+		scalar(column, |v| format!("(typeof({v}) {typ} AND {v} {cmp} {p})"))
+	}
+
+	fn include(&mut self, column: &Column, op: &str, value: Value) -> DbResult<String> {
+		let values = match value {
+			Value::Array(arr) => arr,
+			value => vec![value],
+		};
+		let sql = values.into_iter().map(|it| self.eq(column, op, it)).collect::<DbResult<_>>()?;
+		Ok(any(sql))
+	}
+}
+
+/// `predicate` of the value, it must never be NULL; array of json column matches if any of its scalar elements does
+fn scalar(column: &Column, predicate: impl Fn(&str) -> String) -> String {
+	let e = &column.expr;
+	if column.json {
+		format!(
+			"(CASE json_type({e}) WHEN 'array' THEN EXISTS (SELECT 1 FROM json_each({e}) WHERE type NOT IN ('array', 'object') AND {}) WHEN 'object' THEN 0 ELSE {} END)",
+			predicate("value"),
+			predicate(&format!("json_extract({e}, '$')"))
+		)
+	} else {
+		predicate(e)
+	}
+}
+
+fn is_scalar(value: &Value) -> bool {
+	!matches!(value, Value::Null | Value::Array(_) | Value::Object(_))
+}
+
+fn is_null(column: &Column) -> String {
+	let e = &column.expr;
+	if column.json {
+		format!("({e} IS NULL OR json_type({e}) = 'null')")
+	} else {
+		format!("({e} IS NULL)")
+	}
+}
+
+fn quote_path(key: &str) -> String {
+	format!("'$.\"{}\"'", key.replace('\'', "''").replace('"', "\\\""))
+}
+
+fn all(sql: Vec<String>) -> String {
+	if sql.is_empty() {
+		String::from("1")
+	} else {
+		format!("({})", sql.join(" AND "))
+	}
+}
+
+fn any(sql: Vec<String>) -> String {
+	if sql.is_empty() {
+		String::from("0")
+	} else {
+		format!("({})", sql.join(" OR "))
+	}
+}
@@ -0,0 +1,122 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_value, Map, Value};
+use tracing::debug;
+
+use crate::connection::Repository;
+use crate::error::{DbError, DbResult};
+use crate::util::{from_row, named_params, quote};
+
+/// Rows of `L` combined with rows of `R` having the same value in column `on`, built by [crate::DbHandler::join].
+/// Rows come in the order they were inserted into `L`, then into `R`.
+pub struct Join<'a, L: Serialize + DeserializeOwned, R: Serialize + DeserializeOwned> {
+	left: Repository<'a, L>,
+	right: Repository<'a, R>,
+	on: String,
+	left_filter: Value,
+	right_filter: Value,
+}
+
+/// Columns of `L` row and of matched `R` row
+type JoinedRow = (Map<String, Value>, Option<Map<String, Value>>);
+
+enum Kind {
+	Inner,
+	Left,
+	Anti,
+}
+
+impl<'a, L: Serialize + DeserializeOwned, R: Serialize + DeserializeOwned> Join<'a, L, R> {
+	pub(crate) fn new(left: Repository<'a, L>, right: Repository<'a, R>, on: &str) -> DbResult<Self> {
+		for (table, fields) in [(&left.table().name, &left.table().fields), (&right.table().name, &right.table().fields)] {
+			if !fields.iter().any(|it| it.name == on) {
+				return Err(DbError::UnknownField { table: table.clone(), field: on.to_string() });
+			}
+		}
+		Ok(Self { left, right, on: on.to_string(), left_filter: Value::Object(Map::new()), right_filter: Value::Object(Map::new()) })
+	}
+
+	/// Json filter of `L` rows
+	pub fn filter(mut self, filter: Value) -> Self {
+		self.left_filter = filter;
+		self
+	}
+
+	/// Json filter of `R` rows, part of the join condition so it doesn't turn left or anti join into inner join
+	pub fn filter_right(mut self, filter: Value) -> Self {
+		self.right_filter = filter;
+		self
+	}
+
+	/// Pairs of matched rows
+	pub fn inner(self) -> DbResult<Vec<(L, R)>> {
+		self.rows(Kind::Inner)?.into_iter()
+			.map(|(l, r)| Ok((from_value(Value::Object(l))?, from_value(Value::Object(r.unwrap_or_default()))?)))
+			.collect()
+	}
+
+	/// Every `L` row with each matched `R` row, or [None] when nothing matches
+	pub fn left(self) -> DbResult<Vec<(L, Option<R>)>> {
+		self.rows(Kind::Left)?.into_iter()
+			.map(|(l, r)| Ok((from_value(Value::Object(l))?, r.map(|it| from_value(Value::Object(it))).transpose()?)))
+			.collect()
+	}
+
+	/// `L` rows without any matched `R` row
+	pub fn anti(self) -> DbResult<Vec<L>> {
+		self.rows(Kind::Anti)?.into_iter().map(|(l, _)| Ok(from_value(Value::Object(l))?)).collect()
+	}
+
+	fn rows(self, kind: Kind) -> DbResult<Vec<JoinedRow>> {
+		let (left, right) = (self.left.table(), self.right.table());
+		let (lf, mut params) = self.left.compile_filter_aliased("l", self.left_filter)?;
+		let (rf, right_params) = self.right.compile_filter_aliased("r", self.right_filter)?;
+		params.extend(right_params);
+
+		// columns are prefixed with alias of their table, `rowid` of the right table is null when nothing matches
+		let mut columns = vec![String::from("l.rowid AS \"l.rowid\""), String::from("r.rowid AS \"r.rowid\"")];
+		columns.extend(left.fields.iter().map(|it| format!("l.{} AS {}", quote(&it.name), quote(&format!("l.{}", it.name)))));
+		if !matches!(kind, Kind::Anti) {
+			columns.extend(right.fields.iter().map(|it| format!("r.{} AS {}", quote(&it.name), quote(&format!("r.{}", it.name)))));
+		}
+		let join = if matches!(kind, Kind::Inner) { "JOIN" } else { "LEFT JOIN" };
+		let on = quote(&self.on);
+		let mut sql = format!(
+			"SELECT {} FROM {} AS l {join} {} AS r ON l.{on} = r.{on} AND ({rf}) WHERE ({lf})",
+			columns.join(","), quote(&left.name), quote(&right.name)
+		);
+		if matches!(kind, Kind::Anti) {
+			sql.push_str(" AND r.rowid IS NULL");
+		}
+		sql.push_str(" ORDER BY l.rowid, r.rowid");
+
+		debug!("{}", sql);
+		let mut stmt = self.left.connection().prepare_cached(&sql)?;
+		let mut rows = stmt.query(named_params(&params).as_slice())?;
+		let mut res = Vec::new();
+		while let Some(row) = rows.next()? {
+			let Value::Object(map) = from_row(row)? else {
+				continue;
+			};
+			let (mut l, mut r) = (Map::new(), Map::new());
+			for (key, value) in map {
+				if let Some(column) = key.strip_prefix("l.") {
+					l.insert(column.to_string(), value);
+				} else if let Some(column) = key.strip_prefix("r.") {
+					r.insert(column.to_string(), value);
+				}
+			}
+			let matched = r.get("rowid").map(|it| !it.is_null()).unwrap_or_default();
+			if !left.rowid {
+				l.remove("rowid");
+			}
+			if !right.rowid {
+				r.remove("rowid");
+			}
+			left.restore(&mut l);
+			right.restore(&mut r);
+			res.push((l, matched.then_some(r)));
+		}
+		Ok(res)
+	}
+}
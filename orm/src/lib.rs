@@ -0,0 +1,27 @@
+//! Store serde structs in SQLite tables and query them with json filters,
+//! table schema is derived from the [serde::Deserialize] impl of the struct.
+
+pub use connection::*;
+pub use error::{DbError, DbResult};
+pub use field::{Field, FieldType};
+pub use filter::FilterCompiler;
+pub use join::Join;
+pub use matcher::obj_match;
+pub use migration::Migration;
+pub use query::Query;
+pub use ser::SerError;
+pub use table::{Table, TableOptions};
+pub use util::Fnv1a;
+
+mod connection;
+mod error;
+mod field;
+mod filter;
+mod join;
+mod matcher;
+mod migration;
+mod query;
+mod schema;
+mod ser;
+mod table;
+mod util;
@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+/// Whether `value` (usually a row) matches json `filter`, every key of the filter is a field of `value`,
+/// see [crate::FilterCompiler] for the same filter in SQL
+pub fn obj_match(filter: &Value, value: &Value) -> bool {
+	match filter {
+		Value::Object(map) => map.iter().all(|(key, pattern)| match key.strip_prefix('$') {
+			Some(op @ ("and" | "or" | "nor" | "not")) => logical_match(op, pattern, |it| obj_match(it, value)),
+			Some(_) => false,
+			None => pattern_match(pattern, field(value, key)),
+		}),
+		Value::Array(arr) => arr.iter().any(|it| obj_match(it, value)),
+		_ => false,
+	}
+}
+
+/// `$and`, `$or` and `$nor` of a list (single value is a list of itself) or `$not` of `arg`
+fn logical_match(op: &str, arg: &Value, matches: impl Fn(&Value) -> bool) -> bool {
+	let args = match arg {
+		Value::Array(arr) => arr.as_slice(),
+		arg => std::slice::from_ref(arg),
+	};
+	match op {
+		"and" => args.iter().all(matches),
+		"or" => args.iter().any(matches),
+		"nor" => !args.iter().any(matches),
+		_ => !matches(arg),
+	}
+}
+
+/// Missing field is null
+fn field<'a>(value: &'a Value, key: &str) -> &'a Value {
+	value.get(key).unwrap_or(&Value::Null)
+}
+
+fn pattern_match(pattern: &Value, value: &Value) -> bool {
+	match pattern {
+		Value::Null => value.is_null(),
+		Value::Array(arr) => arr.iter().any(|it| pattern_match(it, value)),
+		Value::Object(map) => map.iter().all(|(key, arg)| match key.strip_prefix('$') {
+			Some(op) => operator_match(op, arg, value),
+			None => pattern_match(arg, field(value, key)),
+		}),
+		Value::String(s) if s.contains(['*', '?']) => {
+			let pattern = wildmatch::WildMatch::new(s);
+			scalar_match(value, |v| v.as_str().map(|it| pattern.matches(it)).unwrap_or_default())
+		}
+		pattern => eq_match(pattern, value),
+	}
+}
+
+fn operator_match(op: &str, arg: &Value, value: &Value) -> bool {
+	match op {
+		"and" | "or" | "nor" | "not" => logical_match(op, arg, |it| pattern_match(it, value)),
+		"eq" | "contains" => eq_match(arg, value),
+		"ne" => !eq_match(arg, value),
+		"lt" | "lte" | "gt" | "gte" => scalar_match(value, |v| match compare(v, arg) {
+			Some(ordering) => match op {
+				"lt" => ordering.is_lt(),
+				"lte" => ordering.is_le(),
+				"gt" => ordering.is_gt(),
+				_ => ordering.is_ge(),
+			},
+			None => false,
+		}),
+		"in" => include_match(arg, value),
+		"nin" => !include_match(arg, value),
+		"like" | "glob" | "regex" => {
+			let Some(pattern) = arg.as_str() else {
+				return false;
+			};
+			let matches: Box<dyn Fn(&str) -> bool> = match op {
+				"like" => Box::new(|it| like(pattern, it)),
+				"glob" => {
+					let pattern = wildmatch::WildMatch::new(pattern);
+					Box::new(move |it| pattern.matches(it))
+				}
+				_ => match regex_lite::Regex::new(pattern) {
+					Ok(regex) => Box::new(move |it| regex.is_match(it)),
+					Err(_) => return false,
+				},
+			};
+			scalar_match(value, |v| v.as_str().map(&matches).unwrap_or_default())
+		}
+		"exists" => arg.as_bool().unwrap_or_default() != value.is_null(),
+		_ => false,
+	}
+}
+
+/// Equality without wildcard, arrays and objects are never equal
+fn eq_match(arg: &Value, value: &Value) -> bool {
+	match arg {
+		Value::Null => value.is_null(),
+		Value::Array(_) | Value::Object(_) => false,
+		arg => scalar_match(value, |v| compare(v, arg).map(Ordering::is_eq).unwrap_or_default()),
+	}
+}
+
+fn include_match(arg: &Value, value: &Value) -> bool {
+	match arg {
+		Value::Array(arr) => arr.iter().any(|it| eq_match(it, value)),
+		arg => eq_match(arg, value),
+	}
+}
+
+/// `predicate` of the value, array matches if any of its scalar elements does
+fn scalar_match(value: &Value, predicate: impl Fn(&Value) -> bool) -> bool {
+	match value {
+		Value::Array(arr) => arr.iter().filter(|it| !it.is_array() && !it.is_object()).any(predicate),
+		Value::Object(_) => false,
+		value => predicate(value),
+	}
+}
+
+/// Strings and numbers are only comparable with the same type, `bool` is a number like in sqlite
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+	fn number(value: &Value) -> Option<f64> {
+		match value {
+			Value::Number(n) => n.as_f64(),
+			Value::Bool(b) => Some(*b as u8 as f64),
+			_ => None,
+		}
+	}
+	match (left, right) {
+		(Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+		_ => number(left)?.partial_cmp(&number(right)?),
+	}
+}
+
+/// `LIKE` of sqlite, `%` is any sequence and `_` is any character, ASCII letters are case-insensitive
+fn like(pattern: &str, text: &str) -> bool {
+	fn like_chars(pattern: &[char], text: &[char]) -> bool {
+		match pattern.split_first() {
+			None => text.is_empty(),
+			Some(('%', rest)) => (0..=text.len()).any(|i| like_chars(rest, &text[i..])),
+			Some((&p, rest)) => match text.split_first() {
+				Some((&t, text)) => (p == '_' || p.eq_ignore_ascii_case(&t)) && like_chars(rest, text),
+				None => false,
+			},
+		}
+	}
+	like_chars(&pattern.chars().collect::<Vec<_>>(), &text.chars().collect::<Vec<_>>())
+}
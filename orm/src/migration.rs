@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter};
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::error::{DbError, DbResult};
+use crate::table::Table;
+use crate::util::{quote, quote_all};
+
+/// Fingerprint of every table created by [crate::Repository], see [Table::fingerprint]
+const SCHEMA_TABLE: &str = "_schema";
+
+/// What happens to existing table whose fingerprint doesn't match the struct anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Migration {
+	/// Rebuild the table and copy rows, values of dropped columns are lost and added columns are NULL.
+	/// Values of columns whose type changed are converted by type affinity of sqlite.
+	/// Fails if an added column isn't nullable and the table has rows.
+	Migrate,
+	/// Drop the table with its rows, for tables filled from elsewhere like caches
+	Reset,
+}
+
+/// Columns of existing table compared with columns of the struct,
+/// every list is empty when only constraints, indexes or collations changed
+#[derive(Debug, Default)]
+pub(crate) struct SchemaDiff {
+	pub added: Vec<String>,
+	pub dropped: Vec<String>,
+	/// declared type changed
+	pub changed: Vec<String>,
+}
+
+impl Display for SchemaDiff {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		if self.added.is_empty() && self.dropped.is_empty() && self.changed.is_empty() {
+			return write!(f, "constraints or indexes changed");
+		}
+		let kinds = [("added", &self.added), ("dropped", &self.dropped), ("changed", &self.changed)];
+		let parts = kinds.iter()
+			.filter(|(_, columns)| !columns.is_empty())
+			.map(|(kind, columns)| format!("{kind} {}", columns.join(",")))
+			.collect::<Vec<_>>();
+		write!(f, "{}", parts.join(", "))
+	}
+}
+
+/// Fingerprint recorded for table `name`, [None] for table that doesn't exist
+/// or was created before fingerprints were recorded
+pub(crate) fn recorded(connection: &Connection, name: &str) -> DbResult<Option<String>> {
+	execute(connection, &format!("CREATE TABLE IF NOT EXISTS {SCHEMA_TABLE} (name TEXT PRIMARY KEY, fingerprint TEXT NOT NULL)"))?;
+	let sql = format!("SELECT fingerprint FROM {SCHEMA_TABLE} WHERE name = ?");
+	Ok(connection.query_row(&sql, [name], |row| row.get(0)).optional()?)
+}
+
+pub(crate) fn record(connection: &Connection, name: &str, fingerprint: &str) -> DbResult<()> {
+	let sql = format!("INSERT INTO {SCHEMA_TABLE} (name, fingerprint) VALUES (?, ?) ON CONFLICT (name) DO UPDATE SET fingerprint = excluded.fingerprint");
+	debug!("{}", sql);
+	connection.execute(&sql, [name, fingerprint])?;
+	Ok(())
+}
+
+/// Difference between existing table and `table`, [None] when the table doesn't exist
+pub(crate) fn diff<T: Serialize + DeserializeOwned>(connection: &Connection, table: &Table<T>) -> DbResult<Option<SchemaDiff>> {
+	let mut stmt = connection.prepare(&format!("PRAGMA table_info({})", quote(&table.name)))?;
+	let existing = stmt.query_map([], |row| Ok((row.get::<_, String>("name")?, row.get::<_, String>("type")?)))?
+		.collect::<rusqlite::Result<Vec<_>>>()?;
+	if existing.is_empty() {
+		return Ok(None);
+	}
+	let mut diff = SchemaDiff::default();
+	for field in &table.fields {
+		match existing.iter().find(|(name, _)| *name == field.name) {
+			None => diff.added.push(field.name.clone()),
+			Some((_, typ)) if !typ.eq_ignore_ascii_case(field.typ.sqlite_type()) => diff.changed.push(field.name.clone()),
+			Some(_) => {}
+		}
+	}
+	diff.dropped = existing.into_iter()
+		.map(|(name, _)| name)
+		.filter(|name| !table.fields.iter().any(|it| it.name == *name))
+		.collect();
+	Ok(Some(diff))
+}
+
+/// Replace existing table with table of the current schema according to [Table::migration],
+/// indexes and full-text index are left to be created again
+pub(crate) fn migrate<T: Serialize + DeserializeOwned>(connection: &Connection, table: &Table<T>, diff: &SchemaDiff) -> DbResult<()> {
+	let name = quote(&table.name);
+	let required = table.fields.iter().find(|it| diff.added.contains(&it.name) && !it.typ.is_nullable());
+	if let Some(field) = required.filter(|_| table.migration == Migration::Migrate) {
+		let empty: bool = connection.query_row(&format!("SELECT NOT EXISTS (SELECT 1 FROM {name})"), [], |row| row.get(0))?;
+		if !empty {
+			return Err(DbError::Schema(format!("Can't add column `{}` to {} without value, use Migration::Reset or make it Option", field.name, table.name)));
+		}
+	}
+	for script in table.drop_fts_scripts() {
+		execute(connection, &script)?;
+	}
+	match table.migration {
+		Migration::Reset => {
+			execute(connection, &format!("DROP TABLE {name}"))?;
+		}
+		Migration::Migrate => {
+			let old = quote(&format!("{}_old", table.name));
+			execute(connection, &format!("ALTER TABLE {name} RENAME TO {old}"))?;
+			execute(connection, &table.create_table_script())?;
+			// `rowid` is kept so rows stay in insertion order
+			let columns = quote_all(["rowid"].into_iter()
+				.chain(table.fields.iter().filter(|it| !diff.added.contains(&it.name)).map(|it| it.name.as_str())));
+			execute(connection, &format!("INSERT INTO {name} ({columns}) SELECT {columns} FROM {old}"))?;
+			execute(connection, &format!("DROP TABLE {old}"))?;
+		}
+	}
+	Ok(())
+}
+
+fn execute(connection: &Connection, sql: &str) -> DbResult<usize> {
+	debug!("{}", sql);
+	Ok(connection.execute(sql, [])?)
+}
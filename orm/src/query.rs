@@ -0,0 +1,158 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_value, Map, Value};
+
+use crate::connection::Repository;
+use crate::error::{DbError, DbResult};
+use crate::util::{quote, quote_all};
+
+/// `SELECT` built from json filter with ordering, pagination and projection.
+/// `$sort`, `$limit`, `$offset` and `$fields` keys of the filter document work like the methods with the same name.
+pub struct Query<'r, 'a, T: Serialize + DeserializeOwned> {
+	repo: &'r Repository<'a, T>,
+	filter: Value,
+	/// column and whether it's descending
+	order: Vec<(String, bool)>,
+	limit: Option<usize>,
+	offset: Option<usize>,
+	fields: Vec<String>,
+	/// invalid option in filter document, reported when the query runs
+	error: Option<DbError>,
+}
+
+impl<'r, 'a, T: Serialize + DeserializeOwned> Query<'r, 'a, T> {
+	pub(crate) fn new(repo: &'r Repository<'a, T>) -> Self {
+		Self { repo, filter: Value::Object(Map::new()), order: Vec::new(), limit: None, offset: None, fields: Vec::new(), error: None }
+	}
+
+	/// Json filter of rows, every row by default
+	pub fn filter(mut self, filter: Value) -> Self {
+		let Value::Object(mut map) = filter else {
+			self.filter = filter;
+			return self;
+		};
+		if let Some(sort) = map.remove("$sort") {
+			match sort {
+				Value::String(column) => self = self.order_by(&column),
+				Value::Array(columns) => {
+					for column in columns {
+						match column {
+							Value::String(column) => self = self.order_by(&column),
+							column => self.mismatch("$sort", "column names", column),
+						}
+					}
+				}
+				// `{"name": 1, "version": -1}`, `preserve_order` of serde_json keeps priority of keys
+				Value::Object(columns) => {
+					for (column, direction) in columns {
+						match direction.as_i64() {
+							Some(1) => self.order.push((column, false)),
+							Some(-1) => self.order.push((column, true)),
+							_ => self.mismatch("$sort", "1 or -1", direction),
+						}
+					}
+				}
+				sort => self.mismatch("$sort", "column names", sort),
+			}
+		}
+		if let Some(limit) = map.remove("$limit") {
+			match limit.as_u64() {
+				Some(n) => self.limit = Some(n as usize),
+				None => self.mismatch("$limit", "non-negative integer", limit),
+			}
+		}
+		if let Some(offset) = map.remove("$offset") {
+			match offset.as_u64() {
+				Some(n) => self.offset = Some(n as usize),
+				None => self.mismatch("$offset", "non-negative integer", offset),
+			}
+		}
+		match map.remove("$fields") {
+			Some(Value::Array(fields)) if fields.iter().all(Value::is_string) => {
+				self.fields = fields.into_iter().filter_map(|it| it.as_str().map(String::from)).collect();
+			}
+			Some(fields) => self.mismatch("$fields", "column names", fields),
+			None => {}
+		}
+		self.filter = Value::Object(map);
+		self
+	}
+
+	/// Sort by `column` after previous columns, prefix with `-` for descending order
+	pub fn order_by(mut self, column: &str) -> Self {
+		match column.strip_prefix('-') {
+			Some(column) => self.order.push((column.to_string(), true)),
+			None => self.order.push((column.to_string(), false)),
+		}
+		self
+	}
+
+	pub fn limit(mut self, limit: usize) -> Self {
+		self.limit = Some(limit);
+		self
+	}
+
+	pub fn offset(mut self, offset: usize) -> Self {
+		self.offset = Some(offset);
+		self
+	}
+
+	/// Only read `fields`, rows can only be read with [Query::values] unless other fields of `T` have default
+	pub fn select(mut self, fields: &[&str]) -> Self {
+		self.fields = fields.iter().map(|it| it.to_string()).collect();
+		self
+	}
+
+	/// Selected columns in order, every column of the table when nothing is selected
+	pub fn columns(&self) -> Vec<&str> {
+		if self.fields.is_empty() {
+			self.repo.columns()
+		} else {
+			self.fields.iter().map(String::as_str).collect()
+		}
+	}
+
+	pub fn all(self) -> DbResult<Vec<T>> {
+		self.values()?.into_iter().map(|it| Ok(from_value(Value::Object(it))?)).collect()
+	}
+
+	pub fn one(self) -> DbResult<Option<T>> {
+		Ok(self.limit(1).all()?.pop())
+	}
+
+	/// Rows as json objects containing selected columns
+	pub fn values(self) -> DbResult<Vec<Map<String, Value>>> {
+		if let Some(err) = self.error {
+			return Err(err);
+		}
+		let table = self.repo.table();
+		let known = |column: &str| (table.rowid && column == "rowid") || table.fields.iter().any(|it| it.name == column);
+		for column in self.order.iter().map(|it| it.0.as_str()).chain(self.fields.iter().map(String::as_str)) {
+			if !known(column) {
+				return Err(DbError::UnknownField { table: table.name.clone(), field: column.to_string() });
+			}
+		}
+		let (f, params) = self.repo.compile_filter(self.filter)?;
+		let columns = if self.fields.is_empty() { table.select_columns().to_string() } else { quote_all(&self.fields) };
+		let mut sql = format!("SELECT {} FROM {} WHERE {}", columns, quote(&table.name), f);
+		if !self.order.is_empty() {
+			let order = self.order.iter()
+				.map(|(column, desc)| if *desc { format!("{} DESC", quote(column)) } else { quote(column) })
+				.collect::<Vec<_>>();
+			sql.push_str(" ORDER BY ");
+			sql.push_str(&order.join(","));
+		}
+		if self.limit.is_some() || self.offset.is_some() {
+			// negative limit is no limit
+			sql.push_str(&format!(" LIMIT {}", self.limit.map(|it| it as i64).unwrap_or(-1)));
+		}
+		if let Some(offset) = self.offset {
+			sql.push_str(&format!(" OFFSET {offset}"));
+		}
+		self.repo.query_values(&sql, &params)
+	}
+
+	fn mismatch(&mut self, key: &str, expected: &'static str, found: Value) {
+		self.error.get_or_insert(DbError::TypeMismatch { key: key.to_string(), expected, found });
+	}
+}
@@ -0,0 +1,302 @@
+use serde::de::{DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::de::value::{BorrowedStrDeserializer, MapDeserializer, SeqDeserializer};
+use serde::{Deserialize, Deserializer};
+use serde::forward_to_deserialize_any;
+
+use crate::field::{Field, FieldType};
+use crate::ser::SerError;
+
+/// Struct name and fields recorded from `deserialize_struct` of `T`
+pub struct Schema {
+	pub name: &'static str,
+	pub fields: Vec<Field>,
+	/// struct has `rowid: i64` field, it's mapped to `rowid` of sqlite instead of a column
+	pub rowid: bool,
+}
+
+impl Schema {
+	/// Drive [Deserialize] impl of `T` with placeholder values to record requested type of every field,
+	/// fields with `#[serde(skip)]` are not part of the schema.
+	pub fn of<'de, T: Deserialize<'de>>() -> Result<Self, SerError> {
+		let mut schema = None;
+		T::deserialize(StructProbe { schema: &mut schema })?;
+		schema.ok_or_else(|| SerError(String::from("Only struct can be stored as table")))
+	}
+}
+
+struct StructProbe<'a> {
+	schema: &'a mut Option<Schema>,
+}
+
+impl<'de, 'a> Deserializer<'de> for StructProbe<'a> {
+	type Error = SerError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+		Err(SerError(String::from("Only struct can be stored as table")))
+	}
+
+	fn deserialize_struct<V: Visitor<'de>>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+		let mut access = FieldProbe { fields, index: 0, types: Vec::with_capacity(fields.len()) };
+		let value = visitor.visit_map(&mut access)?;
+		let (rowid, fields) = fields.iter().zip(access.types)
+			.map(|(&name, typ)| Field { name: name.to_string(), typ })
+			.partition::<Vec<_>, _>(|it| it.name == "rowid" && it.typ == FieldType::I64);
+		*self.schema = Some(Schema { name, fields, rowid: !rowid.is_empty() });
+		Ok(value)
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map enum identifier ignored_any
+	}
+}
+
+/// Feed every field name of the struct once, value of each field is a [TypeProbe]
+struct FieldProbe {
+	fields: &'static [&'static str],
+	index: usize,
+	types: Vec<FieldType>,
+}
+
+impl<'de> MapAccess<'de> for FieldProbe {
+	type Error = SerError;
+
+	fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+		match self.fields.get(self.index) {
+			Some(&name) => seed.deserialize(BorrowedStrDeserializer::new(name)).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+		let name = self.fields[self.index];
+		self.index += 1;
+		let mut typ = None;
+		let value = seed.deserialize(TypeProbe { typ: &mut typ })
+			.map_err(|err| SerError(format!("{} of field `{name}`", err.0)))?;
+		match typ {
+			Some(typ) => {
+				self.types.push(typ);
+				Ok(value)
+			}
+			None => Err(SerError(format!("Unsupported type of field `{name}`"))),
+		}
+	}
+}
+
+/// Record type requested by the visitor and answer with placeholder value of that type
+struct TypeProbe<'a> {
+	typ: &'a mut Option<FieldType>,
+}
+
+macro_rules! probe {
+    ($method:ident, $visit:ident, $typ:expr, $value:expr) => {
+	    fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		    *self.typ = Some($typ);
+		    visitor.$visit($value)
+	    }
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for TypeProbe<'a> {
+	type Error = SerError;
+
+	probe!(deserialize_bool, visit_bool, FieldType::Bool, false);
+	probe!(deserialize_i8, visit_i8, FieldType::I8, 0);
+	probe!(deserialize_i16, visit_i16, FieldType::I16, 0);
+	probe!(deserialize_i32, visit_i32, FieldType::I32, 0);
+	probe!(deserialize_i64, visit_i64, FieldType::I64, 0);
+	probe!(deserialize_u8, visit_u8, FieldType::U8, 0);
+	probe!(deserialize_u16, visit_u16, FieldType::U16, 0);
+	probe!(deserialize_u32, visit_u32, FieldType::U32, 0);
+	probe!(deserialize_u64, visit_u64, FieldType::U64, 0);
+	probe!(deserialize_f32, visit_f32, FieldType::F32, 0.0);
+	probe!(deserialize_f64, visit_f64, FieldType::F64, 0.0);
+	probe!(deserialize_char, visit_char, FieldType::Char, '\0');
+	probe!(deserialize_str, visit_str, FieldType::String, "");
+	probe!(deserialize_string, visit_string, FieldType::String, String::new());
+	probe!(deserialize_bytes, visit_bytes, FieldType::Bytes, &[]);
+	probe!(deserialize_byte_buf, visit_byte_buf, FieldType::Bytes, Vec::new());
+
+	/// Untyped field like [serde_json::Value], column is declared without type
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Any);
+		visitor.visit_unit()
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		let mut inner = None;
+		let value = visitor.visit_some(TypeProbe { typ: &mut inner })?;
+		*self.typ = inner.map(|it| FieldType::Nullable(Box::new(it)));
+		Ok(value)
+	}
+
+	/// Newtype is stored as its inner type
+	fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Json);
+		visitor.visit_seq(SeqDeserializer::<_, SerError>::new(Vec::<()>::new().into_iter()))
+	}
+
+	fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_unit()
+	}
+
+	fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		self.deserialize_any(visitor)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Json);
+		visitor.visit_enum(VariantProbe { variant: variants.first().copied().unwrap_or_default() })
+	}
+
+	fn deserialize_i128<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+		Err(SerError(String::from("Unsupported type `i128`")))
+	}
+
+	fn deserialize_u128<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+		Err(SerError(String::from("Unsupported type `u128`")))
+	}
+
+	fn deserialize_unit_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_unit()
+	}
+
+	fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Json);
+		visitor.visit_seq(TupleProbe { len })
+	}
+
+	fn deserialize_tuple_struct<V: Visitor<'de>>(self, _: &'static str, len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+		self.deserialize_tuple(len, visitor)
+	}
+
+	fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Json);
+		visitor.visit_map(MapDeserializer::<_, SerError>::new(Vec::<((), ())>::new().into_iter()))
+	}
+
+	/// Nested struct, every field is probed with placeholder value but the whole struct is a single json column
+	fn deserialize_struct<V: Visitor<'de>>(self, _: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+		*self.typ = Some(FieldType::Json);
+		visitor.visit_map(FieldProbe { fields, index: 0, types: Vec::with_capacity(fields.len()) })
+	}
+
+	fn deserialize_identifier<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+		Err(SerError(String::from("Unsupported type `identifier`")))
+	}
+}
+
+/// Placeholder value for every element of tuple
+struct TupleProbe {
+	len: usize,
+}
+
+impl<'de> SeqAccess<'de> for TupleProbe {
+	type Error = SerError;
+
+	fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+		if self.len == 0 {
+			return Ok(None);
+		}
+		self.len -= 1;
+		seed.deserialize(TypeProbe { typ: &mut None }).map(Some)
+	}
+}
+
+/// Placeholder of enum is its first variant
+struct VariantProbe {
+	variant: &'static str,
+}
+
+impl<'de> EnumAccess<'de> for VariantProbe {
+	type Error = SerError;
+	type Variant = Self;
+
+	fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error> {
+		Ok((seed.deserialize(BorrowedStrDeserializer::new(self.variant))?, self))
+	}
+}
+
+impl<'de> VariantAccess<'de> for VariantProbe {
+	type Error = SerError;
+
+	fn unit_variant(self) -> Result<(), Self::Error> {
+		Ok(())
+	}
+
+	fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Self::Error> {
+		seed.deserialize(TypeProbe { typ: &mut None })
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_seq(TupleProbe { len })
+	}
+
+	fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+		visitor.visit_map(FieldProbe { fields, index: 0, types: Vec::with_capacity(fields.len()) })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde::{Deserialize, Serialize};
+
+	use crate::field::FieldType;
+
+	use super::Schema;
+
+	/// Newtype is stored as the inner type
+	#[derive(Serialize, Deserialize)]
+	struct PkgVersion(String);
+
+	#[derive(Serialize, Deserialize)]
+	struct Sample {
+		name: String,
+		installed: Option<PkgVersion>,
+		size: i64,
+		#[serde(skip)]
+		_depends: Vec<String>,
+	}
+
+	#[derive(Serialize, Deserialize)]
+	enum Kind {
+		A(i64, String),
+		B { depends: Vec<String> },
+	}
+
+	#[derive(Serialize, Deserialize)]
+	struct Nested {
+		kind: Kind,
+		sample: Sample,
+		pair: (i64, bool),
+		depends: Option<Vec<String>>,
+	}
+
+	#[derive(Serialize, Deserialize)]
+	struct Wide {
+		wide: i128,
+	}
+
+	#[test]
+	fn schema_without_sample() {
+		let schema = Schema::of::<Sample>().unwrap();
+		assert_eq!(schema.name, "Sample");
+		let fields = schema.fields.iter().map(|it| (it.name.as_str(), &it.typ)).collect::<Vec<_>>();
+		assert_eq!(fields, [
+			("name", &FieldType::String),
+			("installed", &FieldType::Nullable(Box::new(FieldType::String))),
+			("size", &FieldType::I64),
+		]);
+
+		let fields = Schema::of::<Nested>().unwrap().fields.into_iter().map(|it| it.typ).collect::<Vec<_>>();
+		assert_eq!(fields, [FieldType::Json, FieldType::Json, FieldType::Json, FieldType::Nullable(Box::new(FieldType::Json))]);
+
+		assert_eq!(Schema::of::<Wide>().err().unwrap().0, "Unsupported type `i128` of field `wide`");
+	}
+}
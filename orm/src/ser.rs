@@ -1,4 +1,4 @@
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Display, Formatter};
 use serde::ser::StdError;
 
 #[derive(Debug)]
@@ -6,7 +6,7 @@ pub struct SerError(pub String);
 
 impl Display for SerError {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-		Debug::fmt(self, f)
+		f.write_str(&self.0)
 	}
 }
 
@@ -17,3 +17,9 @@ impl serde::ser::Error for SerError {
 		Self(msg.to_string())
 	}
 }
+
+impl serde::de::Error for SerError {
+	fn custom<T>(msg: T) -> Self where T: Display {
+		Self(msg.to_string())
+	}
+}
@@ -0,0 +1,282 @@
+use std::hash::Hasher;
+use std::marker::PhantomData;
+
+use rusqlite::Row;
+use rusqlite::types::Value as SqlValue;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{to_value, Map, Value};
+
+use crate::error::{DbError, DbResult};
+use crate::field::Field;
+use crate::migration::Migration;
+use crate::schema::Schema;
+use crate::util::{from_row, quote, quote_all, value_to_sql, Fnv1a};
+
+/// Name, constraints and indexes of the table storing `Self`, every list contains column names.
+/// Column is named after serialized name of the field, use `#[serde(rename)]` to name it differently.
+pub trait TableOptions {
+	/// Table name instead of struct name with `s` suffix
+	fn table_name() -> Option<&'static str> {
+		None
+	}
+
+	/// `rowid` of sqlite is used when empty
+	fn primary_key() -> &'static [&'static str] {
+		&[]
+	}
+
+	/// Unique composite keys, the first one is conflict target of upsert if there is no primary key
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[]
+	}
+
+	fn indexes() -> &'static [&'static [&'static str]] {
+		&[]
+	}
+
+	/// Text columns compared with collation registered to the connection, e.g. `("version", "PkgVersion")`
+	fn collations() -> &'static [(&'static str, &'static str)] {
+		&[]
+	}
+
+	/// Columns of full-text index with their `bm25` weight, no index when empty
+	fn search() -> &'static [(&'static str, f64)] {
+		&[]
+	}
+
+	/// How existing table is updated when the struct no longer matches it
+	fn migration() -> Migration {
+		Migration::Migrate
+	}
+}
+
+#[derive(Debug)]
+pub struct Table<T: Serialize + DeserializeOwned> {
+	pub name: String,
+	pub fields: Vec<Field>,
+	/// struct has `rowid` field, select it along with other columns
+	pub rowid: bool,
+	pub primary_key: &'static [&'static str],
+	pub unique: &'static [&'static [&'static str]],
+	pub indexes: &'static [&'static [&'static str]],
+	pub search: &'static [(&'static str, f64)],
+	pub migration: Migration,
+	typ: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + TableOptions> Table<T> {
+	/// Table named after the struct with `s` suffix e.g. `Package` is stored in `Packages`,
+	/// `#[serde(rename)]` of the struct and [TableOptions::table_name] are respected
+	pub fn new() -> DbResult<Self> {
+		let schema = Schema::of::<T>()?;
+		let mut table = Table {
+			name: T::table_name().map(String::from).unwrap_or_else(|| format!("{}s", schema.name)),
+			fields: schema.fields,
+			rowid: schema.rowid,
+			primary_key: T::primary_key(),
+			unique: T::unique(),
+			indexes: T::indexes(),
+			search: T::search(),
+			migration: T::migration(),
+			typ: PhantomData,
+		};
+		if table.fields.is_empty() {
+			return Err(DbError::Schema(format!("Table {} has no column", table.name)));
+		}
+		for column in table.primary_key.iter().chain(table.unique.iter().chain(table.indexes).flat_map(|it| it.iter())).chain(table.search.iter().map(|it| &it.0)) {
+			if !table.fields.iter().any(|it| it.name == *column) {
+				return Err(DbError::Schema(format!("Unknown column `{}` in options of {}", column, table.name)));
+			}
+		}
+		for &(column, collation) in T::collations() {
+			let Some(field) = table.fields.iter_mut().find(|it| it.name == column) else {
+				return Err(DbError::Schema(format!("Unknown column `{}` in options of {}", column, table.name)));
+			};
+			field.typ = field.typ.collated(collation)
+				.ok_or_else(|| DbError::Schema(format!("Collation of non-text column `{}` in {}", column, table.name)))?;
+		}
+		Ok(table)
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> Table<T> {
+	/// Columns of `SELECT` and `RETURNING`
+	pub fn select_columns(&self) -> &'static str {
+		if self.rowid { "rowid, *" } else { "*" }
+	}
+
+	/// Primary key or the first unique key
+	pub fn conflict_target(&self) -> Option<&'static [&'static str]> {
+		if self.primary_key.is_empty() {
+			self.unique.first().copied()
+		} else {
+			Some(self.primary_key)
+		}
+	}
+
+	/// Full-text index of [Table::search] columns e.g. `Packages_fts`
+	pub fn fts_name(&self) -> String {
+		format!("{}_fts", self.name)
+	}
+
+	/// External content fts5 table and triggers keeping it in sync with the table,
+	/// empty when there is no [Table::search] column
+	pub fn create_fts_scripts(&self) -> Vec<String> {
+		if self.search.is_empty() {
+			return Vec::new();
+		}
+		let (name, fts) = (quote(&self.name), quote(&self.fts_name()));
+		let columns = quote_all(self.search.iter().map(|it| it.0));
+		let values = |prefix: &str| self.search.iter().map(|it| format!("{prefix}.{}", quote(it.0))).collect::<Vec<_>>().join(",");
+		let delete = format!("INSERT INTO {fts} ({fts},rowid,{columns}) VALUES ('delete',old.rowid,{});", values("old"));
+		let insert = format!("INSERT INTO {fts} (rowid,{columns}) VALUES (new.rowid,{});", values("new"));
+		let [on_insert, on_delete, on_update] = self.fts_trigger_names();
+		vec![
+			format!("CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5 ({columns},content='{}',content_rowid='rowid')", self.name.replace('\'', "''")),
+			format!("CREATE TRIGGER IF NOT EXISTS {on_insert} AFTER INSERT ON {name} BEGIN {insert} END"),
+			format!("CREATE TRIGGER IF NOT EXISTS {on_delete} AFTER DELETE ON {name} BEGIN {delete} END"),
+			format!("CREATE TRIGGER IF NOT EXISTS {on_update} AFTER UPDATE ON {name} BEGIN {delete} {insert} END"),
+		]
+	}
+
+	/// Triggers and full-text index of [Table::create_fts_scripts], dropped whether or not the table has [Table::search] columns
+	pub fn drop_fts_scripts(&self) -> Vec<String> {
+		let mut res = self.fts_trigger_names().map(|it| format!("DROP TRIGGER IF EXISTS {it}")).to_vec();
+		res.push(format!("DROP TABLE IF EXISTS {}", quote(&self.fts_name())));
+		res
+	}
+
+	/// Quoted names of insert, delete and update triggers
+	fn fts_trigger_names(&self) -> [String; 3] {
+		let fts = self.fts_name();
+		["insert", "delete", "update"].map(|it| quote(&format!("{fts}_{it}")))
+	}
+
+	/// Hash of table, index and full-text index definitions, recorded along with the table to detect changes of `T`.
+	pub fn fingerprint(&self) -> String {
+		let mut hasher = Fnv1a::default();
+		let scripts = [self.create_table_script()].into_iter().chain(self.create_index_scripts()).chain(self.create_fts_scripts());
+		for script in scripts {
+			hasher.write(script.as_bytes());
+			// separator, so moving text between scripts changes the hash
+			hasher.write_u8(0);
+		}
+		format!("{:016x}", hasher.finish())
+	}
+
+	/// `bm25` of matched row, lower is better
+	pub fn rank_expr(&self) -> String {
+		let weights = self.search.iter().map(|it| it.1.to_string()).collect::<Vec<_>>();
+		format!("bm25({},{})", quote(&self.fts_name()), weights.join(","))
+	}
+
+	/// Row as json object, integers of boolean columns are converted back to `true`/`false`
+	/// and text of json columns is parsed
+	pub fn read_row(&self, row: &Row<'_>) -> rusqlite::Result<Value> {
+		let mut value = from_row(row)?;
+		if let Value::Object(map) = &mut value {
+			self.restore(map);
+		}
+		Ok(value)
+	}
+
+	/// Convert columns read from sqlite back to json of `T`, see [Table::read_row]
+	pub fn restore(&self, map: &mut Map<String, Value>) {
+		for field in &self.fields {
+			let Some(v) = map.get_mut(&field.name) else {
+				continue;
+			};
+			if field.typ.is_bool() {
+				if let Some(i) = v.as_i64() {
+					*v = Value::Bool(i != 0);
+				}
+			} else if field.typ.is_json() {
+				if let Some(parsed) = v.as_str().and_then(|it| serde_json::from_str(it).ok()) {
+					*v = parsed;
+				}
+			}
+		}
+	}
+
+	/// Named parameters of every column of [Table::insert_script], value of json columns is stored as json text
+	pub fn to_params(&self, obj: &T) -> DbResult<Vec<(String, SqlValue)>> {
+		let Value::Object(mut map) = to_value(obj)? else {
+			return Err(DbError::Schema(format!("Row of {} isn't serialized as object", self.name)));
+		};
+		// parameters are named after position of the column, so any column name can be used
+		let params = self.fields.iter().enumerate()
+			.map(|(i, field)| {
+				let value = match map.remove(&field.name) {
+					Some(v) if field.typ.is_json() && !v.is_null() => SqlValue::from(v.to_string()),
+					Some(v) => value_to_sql(v),
+					None => SqlValue::Null,
+				};
+				(format!(":c{i}"), value)
+			})
+			.collect();
+		Ok(params)
+	}
+
+	pub fn create_table_script(&self) -> String {
+		let mut res = String::new();
+		res.push_str("CREATE TABLE IF NOT EXISTS ");
+		res.push_str(&quote(&self.name));
+		res.push_str(" (");
+		let columns = self.fields.iter()
+			.map(|f| {
+				let mut column = format!("{} {}", quote(&f.name), f.typ.sqlite_type());
+				if let Some(collation) = f.typ.collation() {
+					column.push_str(" COLLATE ");
+					column.push_str(collation);
+				}
+				column
+			})
+			.collect::<Vec<_>>();
+		res.push_str(&columns.join(","));
+		if !self.primary_key.is_empty() {
+			res.push_str(",PRIMARY KEY (");
+			res.push_str(&quote_all(self.primary_key));
+			res.push(')');
+		}
+		for unique in self.unique {
+			res.push_str(",UNIQUE (");
+			res.push_str(&quote_all(*unique));
+			res.push(')');
+		}
+		res.push(')');
+		res
+	}
+
+	/// Index is named after table and its columns e.g. `Packages_repo_name`
+	pub fn create_index_scripts(&self) -> Vec<String> {
+		self.indexes.iter()
+			.map(|columns| {
+				let index = quote(&format!("{}_{}", self.name, columns.join("_")));
+				format!("CREATE INDEX IF NOT EXISTS {index} ON {} ({})", quote(&self.name), quote_all(*columns))
+			})
+			.collect()
+	}
+
+	/// Insert every column, row with the same key as [Table::conflict_target] is updated instead
+	pub fn insert_script(&self, returning: bool) -> String {
+		let columns = self.fields.iter().map(|it| quote(&it.name)).collect::<Vec<_>>();
+		let mut res = format!(
+			"INSERT INTO {} ({}) VALUES ({})",
+			quote(&self.name),
+			columns.join(","),
+			(0..columns.len()).map(|i| format!(":c{i}")).collect::<Vec<_>>().join(",")
+		);
+		if let Some(target) = self.conflict_target() {
+			res.push_str(" ON CONFLICT (");
+			res.push_str(&quote_all(target));
+			res.push_str(") DO UPDATE SET ");
+			res.push_str(&columns.iter().map(|it| format!("{it}=excluded.{it}")).collect::<Vec<_>>().join(","));
+		}
+		if returning {
+			res.push_str(" RETURNING ");
+			res.push_str(self.select_columns());
+		}
+		res
+	}
+}
@@ -0,0 +1,121 @@
+use std::hash::Hasher;
+
+use rusqlite::{Row, Rows, ToSql};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use serde_json::{Map, Number, Value};
+
+/// Quote identifier of table or column, so reserved words like `group` and any character can be used in names
+pub fn quote(ident: &str) -> String {
+	format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Comma separated [quote]d identifiers
+pub fn quote_all<S: AsRef<str>>(idents: impl IntoIterator<Item=S>) -> String {
+	idents.into_iter().map(|it| quote(it.as_ref())).collect::<Vec<_>>().join(",")
+}
+
+pub fn value_to_sql(value: Value) -> SqlValue {
+	match value {
+		Value::Null => {
+			SqlValue::Null
+		}
+		Value::Bool(b) => {
+			SqlValue::from(b)
+		}
+		Value::Number(n) => {
+			if n.is_f64() {
+				SqlValue::from(n.as_f64())
+			} else {
+				SqlValue::from(n.as_i64())
+			}
+		}
+		Value::String(s) => {
+			SqlValue::from(s)
+		}
+		// stored in json column
+		value => {
+			SqlValue::from(value.to_string())
+		}
+	}
+}
+
+pub fn from_rows(mut rows: Rows) -> rusqlite::Result<Value> {
+	let mut arr = Vec::new();
+	while let Some(row) = rows.next()? {
+		arr.push(from_row(row)?);
+	}
+	Ok(Value::Array(arr))
+}
+
+pub fn from_row(row: &Row<'_>) -> rusqlite::Result<Value> {
+	let mut map = Map::new();
+	for x in row.as_ref().column_names() {
+		map.insert(x.to_string(), match row.get_ref(x)? {
+			ValueRef::Null => {
+				Value::Null
+			}
+			ValueRef::Integer(i) => {
+				Value::Number(Number::from(i))
+			}
+			// NaN is null in json
+			ValueRef::Real(i) => {
+				Number::from_f64(i).map(Value::Number).unwrap_or_default()
+			}
+			ValueRef::Text(t) => {
+				Value::String(String::from_utf8_lossy(t).to_string())
+			}
+			ValueRef::Blob(blob) => {
+				Value::Array(blob.iter().map(|&it| Value::Number(Number::from(it))).collect())
+			}
+		});
+	}
+	Ok(Value::Object(map))
+}
+
+/// 64-bit FNV-1a, unlike [std::collections::hash_map::DefaultHasher] its result doesn't change between Rust releases.
+/// Feed it bytes with [Hasher::write], [std::hash::Hash] impls of std types aren't stable either.
+#[derive(Debug, Clone, Copy)]
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+	fn default() -> Self {
+		Self(0xcbf2_9ce4_8422_2325)
+	}
+}
+
+impl Hasher for Fnv1a {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for byte in bytes {
+			self.0 ^= u64::from(*byte);
+			self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+		}
+	}
+}
+
+/// Borrow named parameters for rusqlite
+pub fn named_params(params: &[(String, SqlValue)]) -> Vec<(&str, &dyn ToSql)> {
+	params.iter().map(|it| (it.0.as_str(), &it.1 as &dyn ToSql)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::hash::Hasher;
+
+	use super::Fnv1a;
+
+	#[test]
+	fn fnv1a_reference_values() {
+		let hash = |bytes: &[u8]| {
+			let mut hasher = Fnv1a::default();
+			hasher.write(bytes);
+			hasher.finish()
+		};
+		assert_eq!(hash(b""), 0xcbf29ce484222325);
+		assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+		assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+	}
+}
@@ -0,0 +1,107 @@
+use proptest::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use orm::{db_init, obj_match, FilterCompiler, Table, TableOptions};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Row {
+	name: String,
+	size: i64,
+	enabled: bool,
+	note: Option<String>,
+	tags: Vec<String>,
+}
+
+impl TableOptions for Row {}
+
+fn text() -> impl Strategy<Value=String> {
+	prop::sample::select(vec!["a", "b", "ab", "B", "1"]).prop_map(String::from)
+}
+
+fn row() -> impl Strategy<Value=Row> {
+	(text(), -2..3i64, any::<bool>(), prop::option::of(text()), prop::collection::vec(text(), 0..3))
+		.prop_map(|(name, size, enabled, note, tags)| Row { name, size, enabled, note, tags })
+}
+
+fn scalar() -> impl Strategy<Value=Value> {
+	prop_oneof![
+		Just(Value::Null),
+		any::<bool>().prop_map(Value::from),
+		(-2..3i64).prop_map(Value::from),
+		Just(json!(0.5)),
+		text().prop_map(Value::from),
+		prop::sample::select(vec!["a*", "?", "*B"]).prop_map(Value::from),
+	]
+}
+
+/// Operators with operand of the right type, others are errors
+fn operator() -> impl Strategy<Value=Value> {
+	let eq = prop::sample::select(vec!["$eq", "$ne", "$contains"]);
+	let cmp = prop::sample::select(vec!["$lt", "$lte", "$gt", "$gte"]);
+	let not_null = scalar().prop_filter("null can't be ordered", |it| !it.is_null());
+	prop_oneof![
+		(eq, scalar()).prop_map(|(op, value)| json!({op: value})),
+		(cmp, not_null).prop_map(|(op, value)| json!({op: value})),
+		prop::collection::vec(scalar(), 0..3).prop_map(|it| json!({"$in": it})),
+		prop::collection::vec(scalar(), 0..3).prop_map(|it| json!({"$nin": it})),
+		prop::sample::select(vec!["A%", "_", "%b"]).prop_map(|it| json!({"$like": it})),
+		prop::sample::select(vec!["a*", "?", "*B"]).prop_map(|it| json!({"$glob": it})),
+		prop::sample::select(vec!["^a", "b$", "[0-9]"]).prop_map(|it| json!({"$regex": it})),
+		any::<bool>().prop_map(|it| json!({"$exists": it})),
+	]
+}
+
+fn pattern() -> impl Strategy<Value=Value> {
+	prop_oneof![scalar(), operator()].prop_recursive(3, 16, 3, |inner| {
+		let op = prop::sample::select(vec!["$and", "$or", "$nor"]);
+		prop_oneof![
+			prop::collection::vec(inner.clone(), 0..3).prop_map(Value::from),
+			inner.clone().prop_map(|it| json!({"$not": it})),
+			(op, prop::collection::vec(inner, 0..3)).prop_map(|(op, it)| json!({op: it})),
+		]
+	})
+}
+
+fn document() -> impl Strategy<Value=Value> {
+	let field = prop::sample::select(vec!["name", "size", "enabled", "note", "tags"]);
+	let leaf = prop::collection::btree_map(field, pattern(), 0..3)
+		.prop_map(|it| Value::Object(it.into_iter().map(|(k, v)| (k.to_string(), v)).collect()));
+	leaf.prop_recursive(2, 8, 3, |inner| {
+		let op = prop::sample::select(vec!["$and", "$or", "$nor"]);
+		prop_oneof![
+			prop::collection::vec(inner.clone(), 0..3).prop_map(Value::from),
+			inner.clone().prop_map(|it| json!({"$not": it})),
+			(op, prop::collection::vec(inner, 0..3)).prop_map(|(op, it)| json!({op: it})),
+		]
+	})
+}
+
+#[test]
+fn compile_with_table_schema() {
+	let table = Table::<Row>::new().unwrap();
+	assert_eq!(table.name, "Rows");
+	let (sql, params) = FilterCompiler::compile(&table.name, &table.fields, json!({"name": "a", "size": {"$gt": 1}})).unwrap();
+	// every value is a parameter with unique name
+	assert_eq!(params.iter().map(|it| it.0.as_str()).collect::<Vec<_>>(), [":p0", ":p1"]);
+	assert!(!sql.contains("'a'"));
+}
+
+proptest! {
+	#[test]
+	fn same_result_as_obj_match(rows in prop::collection::vec(row(), 1..6), filter in document()) {
+		let db = db_init(None).unwrap();
+		let repo = db.get_repository::<Row>().unwrap();
+		for row in &rows {
+			repo.add(row.clone()).unwrap();
+		}
+		let expected = rows.iter()
+			.map(|it| serde_json::to_value(it).unwrap())
+			.filter(|it| obj_match(&filter, it))
+			.collect::<Vec<_>>();
+		let found = repo.find(filter.clone()).unwrap().into_iter()
+			.map(|it| serde_json::to_value(it).unwrap())
+			.collect::<Vec<_>>();
+		prop_assert_eq!(found, expected, "{}", filter);
+	}
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use orm::{db_init, DbError, TableOptions};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Sync {
+	repo: String,
+	name: String,
+	version: String,
+	groups: Vec<String>,
+}
+
+impl TableOptions for Sync {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["repo", "name"]]
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Local {
+	name: String,
+	version: String,
+	explicit: bool,
+}
+
+impl TableOptions for Local {}
+
+fn sync(repo: &str, name: &str, version: &str) -> Sync {
+	Sync { repo: String::from(repo), name: String::from(name), version: String::from(version), groups: vec![String::from("base")] }
+}
+
+fn local(name: &str, version: &str, explicit: bool) -> Local {
+	Local { name: String::from(name), version: String::from(version), explicit }
+}
+
+#[test]
+fn inner_left_and_anti() {
+	let db = db_init(None).unwrap();
+	db.get_repository::<Local>().unwrap().insert_all([
+		local("bash", "5.2", true),
+		local("glibc", "2.38", false),
+		local("yay", "12.0", true),
+	]).unwrap();
+	db.get_repository::<Sync>().unwrap().insert_all([
+		sync("core", "bash", "5.2"),
+		sync("core", "glibc", "2.38"),
+		sync("testing", "glibc", "2.39"),
+	]).unwrap();
+
+	let inner = db.join::<Local, Sync>("name").unwrap().inner().unwrap();
+	let pairs = inner.iter().map(|(l, r)| (l.name.as_str(), r.repo.as_str())).collect::<Vec<_>>();
+	assert_eq!(pairs, [("bash", "core"), ("glibc", "core"), ("glibc", "testing")]);
+	// bool and json columns are restored
+	assert_eq!(inner[0], (local("bash", "5.2", true), sync("core", "bash", "5.2")));
+
+	let left = db.join::<Local, Sync>("name").unwrap().filter_right(json!({"repo": "testing"})).left().unwrap();
+	let pairs = left.iter().map(|(l, r)| (l.name.as_str(), r.as_ref().map(|it| it.version.as_str()))).collect::<Vec<_>>();
+	assert_eq!(pairs, [("bash", None), ("glibc", Some("2.39")), ("yay", None)]);
+
+	// installed packages not present in any sync repo
+	let foreign = db.join::<Local, Sync>("name").unwrap().anti().unwrap();
+	assert_eq!(foreign, [local("yay", "12.0", true)]);
+	let outdated = db.join::<Local, Sync>("name").unwrap()
+		.filter(json!({"explicit": false}))
+		.filter_right(json!({"version": {"$gt": "2.38"}}))
+		.inner().unwrap();
+	assert_eq!(outdated.len(), 1);
+	assert_eq!(outdated[0].1.repo, "testing");
+}
+
+#[test]
+fn join_errors() {
+	let db = db_init(None).unwrap();
+	assert!(matches!(db.join::<Local, Sync>("repo"), Err(DbError::UnknownField { field, .. }) if field == "repo"));
+	let join = db.join::<Local, Sync>("name").unwrap().filter_right(json!({"explicit": true}));
+	assert!(matches!(join.inner(), Err(DbError::UnknownField { table, .. }) if table == "Syncs"));
+}
@@ -0,0 +1,199 @@
+use serde_json::json;
+
+use orm::{db_init, DbError, Migration};
+
+/// Every module has its own version of `Item` stored in `Items`
+mod v1 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	pub struct Item {
+		pub name: String,
+		pub size: i64,
+		pub note: String,
+	}
+
+	impl orm::TableOptions for Item {
+		fn search() -> &'static [(&'static str, f64)] {
+			&[("name", 1.0)]
+		}
+	}
+
+	pub fn item(name: &str, size: i64) -> Item {
+		Item { name: String::from(name), size, note: String::from("note") }
+	}
+}
+
+/// `note` dropped, `extra` added
+mod v2 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	pub struct Item {
+		pub name: String,
+		pub size: i64,
+		pub extra: Option<String>,
+	}
+
+	impl orm::TableOptions for Item {
+		fn search() -> &'static [(&'static str, f64)] {
+			&[("name", 1.0)]
+		}
+	}
+}
+
+/// type of `size` changed
+mod v3 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	pub struct Item {
+		pub name: String,
+		pub size: String,
+		pub note: String,
+	}
+
+	impl orm::TableOptions for Item {}
+}
+
+/// required column `count` added
+mod v4 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	pub struct Item {
+		pub name: String,
+		pub size: i64,
+		pub note: String,
+		pub count: i64,
+	}
+
+	impl orm::TableOptions for Item {}
+
+	/// Same columns, rows are dropped instead of migrated
+	pub mod reset {
+		use serde::{Deserialize, Serialize};
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		pub struct Item {
+			pub name: String,
+			pub size: i64,
+			pub note: String,
+			pub count: i64,
+		}
+
+		impl orm::TableOptions for Item {
+			fn migration() -> orm::Migration {
+				orm::Migration::Reset
+			}
+		}
+	}
+}
+
+/// Columns of [v1::Item] with an index and unique key
+mod indexed {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	pub struct Item {
+		pub name: String,
+		pub size: i64,
+		pub note: String,
+	}
+
+	impl orm::TableOptions for Item {
+		fn unique() -> &'static [&'static [&'static str]] {
+			&[&["name"]]
+		}
+
+		fn indexes() -> &'static [&'static [&'static str]] {
+			&[&["size"]]
+		}
+	}
+}
+
+#[test]
+fn add_and_drop_columns() {
+	let db = db_init(None).unwrap();
+	db.get_repository::<v1::Item>().unwrap().insert_all([v1::item("foo", 1), v1::item("bar", 2)]).unwrap();
+
+	let repo = db.get_repository::<v2::Item>().unwrap();
+	let rows = repo.all().unwrap();
+	assert_eq!(rows, [
+		v2::Item { name: String::from("foo"), size: 1, extra: None },
+		v2::Item { name: String::from("bar"), size: 2, extra: None },
+	]);
+	// full-text index is created again with existing rows
+	assert_eq!(repo.search("bar", json!({}), None).unwrap().len(), 1);
+	repo.add(v2::Item { name: String::from("baz"), size: 3, extra: Some(String::from("extra")) }).unwrap();
+	assert_eq!(repo.search("baz", json!({}), None).unwrap().len(), 1);
+
+	// `note` can't be added back to existing rows
+	assert!(db.get_repository::<v1::Item>().is_err());
+}
+
+#[test]
+fn change_column_type() {
+	let db = db_init(None).unwrap();
+	db.get_repository::<v1::Item>().unwrap().add(v1::item("foo", 10)).unwrap();
+
+	let repo = db.get_repository::<v3::Item>().unwrap();
+	assert_eq!(repo.all().unwrap(), [v3::Item { name: String::from("foo"), size: String::from("10"), note: String::from("note") }]);
+	assert_eq!(repo.count(json!({"size": "10"})).unwrap(), 1);
+}
+
+#[test]
+fn add_required_column() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<v1::Item>().unwrap();
+	repo.add(v1::item("foo", 1)).unwrap();
+	assert!(matches!(db.get_repository::<v4::Item>(), Err(DbError::Schema(msg)) if msg.contains("`count`")));
+	// failed migration is rolled back
+	assert_eq!(db.get_repository::<v1::Item>().unwrap().all().unwrap(), [v1::item("foo", 1)]);
+
+	// nothing to fill when the table is empty
+	repo.clear().unwrap();
+	let repo = db.get_repository::<v4::Item>().unwrap();
+	assert!(repo.all().unwrap().is_empty());
+}
+
+#[test]
+fn reset_instead_of_migrate() {
+	assert_eq!(<v1::Item as orm::TableOptions>::migration(), Migration::Migrate);
+	let db = db_init(None).unwrap();
+	db.get_repository::<v1::Item>().unwrap().add(v1::item("foo", 1)).unwrap();
+
+	let repo = db.get_repository::<v4::reset::Item>().unwrap();
+	assert!(repo.all().unwrap().is_empty());
+	repo.add(v4::reset::Item { name: String::from("foo"), size: 1, note: String::new(), count: 1 }).unwrap();
+	assert_eq!(repo.count(json!({})).unwrap(), 1);
+}
+
+#[test]
+fn change_constraints_and_indexes() {
+	let db = db_init(None).unwrap();
+	db.get_repository::<v1::Item>().unwrap().insert_all([v1::item("foo", 1), v1::item("bar", 2)]).unwrap();
+
+	let repo = db.get_repository::<indexed::Item>().unwrap();
+	assert_eq!(repo.count(json!({})).unwrap(), 2);
+	let index: bool = db.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'Items_size')", [], |row| row.get(0)).unwrap();
+	assert!(index);
+	// upsert by the new unique key
+	repo.add(indexed::Item { name: String::from("foo"), size: 5, note: String::new() }).unwrap();
+	assert_eq!(repo.count(json!({})).unwrap(), 2);
+	// full-text index of the first version is gone
+	let fts: bool = db.query_row("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE name LIKE 'Items_fts%')", [], |row| row.get(0)).unwrap();
+	assert!(!fts);
+}
+
+#[test]
+fn table_without_fingerprint() {
+	let db = db_init(None).unwrap();
+	db.execute_batch("CREATE TABLE Items (name TEXT,size BIGINT,note TEXT); INSERT INTO Items VALUES ('foo', 1, 'note')").unwrap();
+
+	let repo = db.get_repository::<v1::Item>().unwrap();
+	assert_eq!(repo.all().unwrap(), [v1::item("foo", 1)]);
+	assert_eq!(repo.search("foo", json!({}), None).unwrap().len(), 1);
+	let recorded: String = db.query_row("SELECT fingerprint FROM _schema WHERE name = 'Items'", [], |row| row.get(0)).unwrap();
+	assert_eq!(recorded.len(), 16);
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use orm::{db_init, DbError, TableOptions};
+
+/// Column names are sql keywords or need quoting
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename = "Select")]
+struct Entry {
+	group: String,
+	#[serde(rename = "order")]
+	position: i64,
+	#[serde(rename = "where clause")]
+	condition: Option<String>,
+	#[serde(rename = "tags\"quoted")]
+	tags: Vec<String>,
+}
+
+impl TableOptions for Entry {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["group", "order"]]
+	}
+
+	fn indexes() -> &'static [&'static [&'static str]] {
+		&[&["order"]]
+	}
+
+	fn search() -> &'static [(&'static str, f64)] {
+		&[("group", 1.0), ("where clause", 1.0)]
+	}
+}
+
+fn entry(group: &str, position: i64) -> Entry {
+	Entry { group: String::from(group), position, condition: None, tags: vec![String::from(group)] }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Group {
+	group: String,
+	from: String,
+}
+
+impl TableOptions for Group {
+	fn table_name() -> Option<&'static str> {
+		Some("table")
+	}
+}
+
+#[test]
+fn reserved_words_and_renames() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Entry>().unwrap();
+	// struct rename is used for table name
+	let tables: Vec<String> = db.prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name LIKE 'Select%'").unwrap()
+		.query_map([], |row| row.get(0)).unwrap()
+		.collect::<Result<_, _>>().unwrap();
+	assert!(tables.contains(&String::from("Selects")), "{tables:?}");
+
+	repo.insert_all([entry("base", 2), entry("devel", 1), entry("base", 1)]).unwrap();
+	// upsert by `group` and `order`
+	repo.add(Entry { condition: Some(String::from("updated")), ..entry("base", 2) }).unwrap();
+	assert_eq!(repo.count(json!({})).unwrap(), 3);
+
+	let orders = |filter| repo.query().filter(filter).all().unwrap().into_iter().map(|it| it.position).collect::<Vec<_>>();
+	assert_eq!(orders(json!({"group": "base", "$sort": "-order"})), [2, 1]);
+	assert_eq!(orders(json!({"where clause": "updated"})), [2]);
+	assert_eq!(orders(json!({"tags\"quoted": "devel"})), [1]);
+	let values = repo.query().select(&["group", "order"]).order_by("group").order_by("order").values().unwrap();
+	assert_eq!(values[0], json!({"group": "base", "order": 1}).as_object().unwrap().clone());
+
+	assert_eq!(repo.update(json!({"group": "devel"}), json!({"order": 5, "where clause": "moved"})).unwrap(), 1);
+	assert_eq!(repo.search("moved", json!({}), None).unwrap()[0].row.position, 5);
+	assert_eq!(repo.delete(json!({"order": {"$lt": 2}})).unwrap(), 1);
+
+	let groups = db.get_repository::<Group>().unwrap();
+	groups.add(Group { group: String::from("base"), from: String::from("core") }).unwrap();
+	assert_eq!(groups.find(json!({"from": "core"})).unwrap().len(), 1);
+	let joined = db.join::<Entry, Group>("group").unwrap().filter_right(json!({"from": "core"})).inner().unwrap();
+	assert_eq!(joined.len(), 1);
+	assert_eq!(joined[0].1.from, "core");
+}
+
+#[test]
+fn unknown_keys_never_reach_sql() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Entry>().unwrap();
+	let injection = "group\" = 'x' OR 1=1 --";
+	assert!(matches!(repo.find(json!({injection: "x"})), Err(DbError::UnknownField { field, .. }) if field == injection));
+	assert!(matches!(repo.query().order_by(injection).all(), Err(DbError::UnknownField { .. })));
+	assert!(matches!(repo.query().select(&["position"]).values(), Err(DbError::UnknownField { field, .. }) if field == "position"));
+	assert!(matches!(repo.update(json!({}), json!({injection: 1})), Err(DbError::UnknownField { .. })));
+	assert!(matches!(db.join::<Entry, Group>(injection), Err(DbError::UnknownField { .. })));
+}
@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use orm::{db_init, obj_match, DbError, TableOptions};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+struct Flag {
+	name: String,
+	enabled: bool,
+	note: Option<String>,
+	weight: Option<i64>,
+}
+
+impl TableOptions for Flag {}
+
+#[test]
+fn option_and_bool_columns() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Flag>().unwrap();
+	let on = || Flag { name: String::from("on"), enabled: true, note: Some(String::from("note")), weight: Some(3) };
+	let off = || Flag { name: String::from("off"), enabled: false, note: None, weight: None };
+	assert_eq!(repo.add(on()).unwrap(), on());
+	repo.add(off()).unwrap();
+
+	let names = |filter| repo.find(filter).unwrap().into_iter().map(|it| it.name).collect::<Vec<_>>();
+	assert_eq!(names(json!({"enabled": true})), ["on"]);
+	assert_eq!(names(json!({"enabled": false})), ["off"]);
+	assert_eq!(names(json!({"note": null})), ["off"]);
+	assert_eq!(repo.all().unwrap(), [on(), off()]);
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Reason {
+	Explicit,
+	Dependency { of: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Pkg {
+	name: String,
+	depends: Vec<String>,
+	reason: Reason,
+}
+
+impl TableOptions for Pkg {}
+
+#[test]
+fn json_columns() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Pkg>().unwrap();
+	let pacman = || Pkg { name: String::from("pacman"), depends: vec![String::from("glibc"), String::from("bash")], reason: Reason::Explicit };
+	let bash = || Pkg { name: String::from("bash"), depends: vec![String::from("glibc")], reason: Reason::Dependency { of: String::from("pacman") } };
+	repo.add(pacman()).unwrap();
+	repo.add(bash()).unwrap();
+	assert_eq!(repo.all().unwrap(), [pacman(), bash()]);
+
+	let names = |filter| repo.find(filter).unwrap().into_iter().map(|it| it.name).collect::<Vec<_>>();
+	assert_eq!(names(json!({"depends": {"$contains": "glibc"}})), ["pacman", "bash"]);
+	assert_eq!(names(json!({"depends": {"$contains": "bash"}})), ["pacman"]);
+	assert_eq!(names(json!({"depends": "bash"})), ["pacman"]);
+	// array is any of its elements
+	assert_eq!(names(json!({"depends": ["glibc"]})), ["pacman", "bash"]);
+	assert_eq!(names(json!({"reason": "Explicit"})), ["pacman"]);
+}
+
+#[test]
+fn filter_operators() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Pkg>().unwrap();
+	repo.add(Pkg { name: String::from("pacman"), depends: vec![String::from("glibc"), String::from("bash")], reason: Reason::Explicit }).unwrap();
+	repo.add(Pkg { name: String::from("bash"), depends: vec![String::from("glibc")], reason: Reason::Dependency { of: String::from("pacman") } }).unwrap();
+	repo.add(Pkg { name: String::from("glibc"), depends: Vec::new(), reason: Reason::Dependency { of: String::from("bash") } }).unwrap();
+
+	let names = |filter| repo.find(filter).unwrap().into_iter().map(|it| it.name).collect::<Vec<_>>();
+	assert_eq!(names(json!({"name": {"$in": ["bash", "zsh"]}})), ["bash"]);
+	assert_eq!(names(json!({"name": {"$nin": ["bash", "zsh"]}})), ["pacman", "glibc"]);
+	assert_eq!(names(json!({"name": {"$like": "PAC%"}})), ["pacman"]);
+	assert_eq!(names(json!({"name": {"$glob": "*ba?h"}})), ["bash"]);
+	assert_eq!(names(json!({"name": {"$regex": "^g.*c$"}})), ["glibc"]);
+	assert_eq!(names(json!({"depends": {"$regex": "^ba"}})), ["pacman"]);
+	assert_eq!(names(json!({"reason": {"Dependency": {"of": "bash"}}})), ["glibc"]);
+	assert_eq!(names(json!({"reason": {"Dependency": {"$exists": true}}})), ["bash", "glibc"]);
+	// top level array is any of the filters
+	assert_eq!(names(json!([{"name": "bash"}, {"name": "glibc"}])), ["bash", "glibc"]);
+	// same field in several operators
+	assert_eq!(names(json!({"$or": [{"name": "bash"}, {"name": "glibc"}], "$not": {"name": "glibc"}})), ["bash"]);
+	assert_eq!(names(json!({"name": {"$not": {"$in": ["bash"]}}, "$nor": [{"name": "glibc"}]})), ["pacman"]);
+	assert_eq!(names(json!({"depends": {"$and": ["glibc", {"$ne": "bash"}]}})), ["bash"]);
+	// string is never equal to number
+	assert!(names(json!({"name": 1})).is_empty());
+}
+
+#[test]
+fn filter_errors() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Flag>().unwrap();
+	let err = |filter| repo.find(filter).unwrap_err();
+	assert!(matches!(err(json!({"missing": null})), DbError::UnknownField { field, .. } if field == "missing"));
+	assert!(matches!(err(json!({"weight": {"nested": 1}})), DbError::UnknownField { field, .. } if field == "weight.nested"));
+	assert!(matches!(err(json!({"name": {"$unknown": 1}})), DbError::InvalidOperator(op) if op == "$unknown"));
+	assert!(matches!(err(json!({"$where": "1"})), DbError::InvalidOperator(_)));
+	assert!(matches!(err(json!({"note": {"$regex": "("}})), DbError::TypeMismatch { .. }));
+	assert!(matches!(err(json!({"note": {"$like": 1}})), DbError::TypeMismatch { .. }));
+	assert!(matches!(err(json!({"weight": {"$eq": [3]}})), DbError::TypeMismatch { .. }));
+	assert!(matches!(err(json!({"weight": {"$lt": null}})), DbError::TypeMismatch { .. }));
+	assert!(matches!(err(json!({"weight": {"$exists": 1}})), DbError::TypeMismatch { .. }));
+	assert!(matches!(err(json!("on")), DbError::TypeMismatch { .. }));
+	assert_eq!(err(json!({"missing": 1})).to_string(), "Unknown field `missing` of table Flags");
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct UserId(String);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct User {
+	id: UserId,
+	name: String,
+}
+
+impl TableOptions for User {
+	fn collations() -> &'static [(&'static str, &'static str)] {
+		&[("name", "NOCASE")]
+	}
+}
+
+#[test]
+fn newtype_and_collated_columns() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<User>().unwrap();
+	repo.add(User { id: UserId(String::from("u2")), name: String::from("bob") }).unwrap();
+	repo.add(User { id: UserId(String::from("u1")), name: String::from("Alice") }).unwrap();
+	// newtype is stored as its inner type without collation
+	assert_eq!(repo.find(json!({"id": "u1"})).unwrap()[0].name, "Alice");
+	let sorted = repo.query().order_by("name").all().unwrap();
+	assert_eq!(sorted.into_iter().map(|it| it.name).collect::<Vec<_>>(), ["Alice", "bob"]);
+	assert_eq!(repo.count(json!({"name": {"$gt": "BOB"}})).unwrap(), 0);
+
+	#[derive(Serialize, Deserialize)]
+	struct Bad {
+		size: i64,
+	}
+	impl TableOptions for Bad {
+		fn collations() -> &'static [(&'static str, &'static str)] {
+			&[("size", "NOCASE")]
+		}
+	}
+	assert!(matches!(db.get_repository::<Bad>(), Err(DbError::Schema(_))));
+}
+
+#[test]
+fn obj_match_examples() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Flag>().unwrap();
+	let flags = [
+		Flag { name: String::from("on"), enabled: true, note: Some(String::from("Note")), weight: Some(3) },
+		Flag { name: String::from("off"), enabled: false, note: None, weight: None },
+		Flag { name: String::from("1"), enabled: true, note: Some(String::from("10")), weight: Some(-1) },
+	];
+	for flag in &flags {
+		repo.add(flag.clone()).unwrap();
+	}
+	let filters = [
+		json!({}),
+		json!({"name": "o*"}),
+		json!({"name": 1}),
+		json!({"name": ["on", "1"]}),
+		json!({"enabled": 1}),
+		json!({"enabled": {"$gt": false}}),
+		json!({"note": null}),
+		json!({"note": {"$ne": null}}),
+		json!({"note": {"$gte": "A", "$lt": "a"}}),
+		json!({"note": {"$lt": 100}}),
+		json!({"note": {"$like": "n_t%"}}),
+		json!({"note": {"$regex": "[0-9]+"}}),
+		json!({"weight": {"$in": [3, null]}}),
+		json!({"weight": {"$nin": [3]}}),
+		json!({"weight": {"$lte": 3.0}}),
+		json!([{"name": "on"}, {"weight": null}]),
+	];
+	for filter in filters {
+		let expected = flags.iter()
+			.filter(|it| obj_match(&filter, &serde_json::to_value(it).unwrap()))
+			.map(|it| it.name.as_str())
+			.collect::<Vec<_>>();
+		let names = repo.find(filter.clone()).unwrap().into_iter().map(|it| it.name).collect::<Vec<_>>();
+		assert_eq!(names, expected, "{filter}");
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Sync {
+	rowid: i64,
+	repo: String,
+	name: String,
+	version: String,
+}
+
+impl TableOptions for Sync {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["repo", "name"]]
+	}
+
+	fn indexes() -> &'static [&'static [&'static str]] {
+		&[&["name"]]
+	}
+}
+
+#[test]
+fn upsert_by_unique_key() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Sync>().unwrap();
+	let sync = |repo: &str, version: &str| Sync { rowid: 0, repo: String::from(repo), name: String::from("glibc"), version: String::from(version) };
+	assert_eq!(repo.add(sync("core", "2.36-6")).unwrap().rowid, 1);
+	assert_eq!(repo.add(sync("testing", "2.37-1")).unwrap().rowid, 2);
+	// same repo and name, row is updated in place
+	let updated = repo.add(sync("core", "2.37-1")).unwrap();
+	assert_eq!(updated, Sync { rowid: 1, ..sync("core", "2.37-1") });
+	assert_eq!(repo.all().unwrap().len(), 2);
+
+	let indexes: i64 = db.query_row("SELECT count(*) FROM sqlite_master WHERE type = 'index' AND name = 'Syncs_name'", [], |row| row.get(0)).unwrap();
+	assert_eq!(indexes, 1);
+}
+
+#[test]
+fn update_and_delete() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Pkg>().unwrap();
+	for name in ["glibc", "bash", "pacman"] {
+		repo.add(Pkg { name: String::from(name), depends: Vec::new(), reason: Reason::Explicit }).unwrap();
+	}
+	assert_eq!(repo.count(json!({})).unwrap(), 3);
+	assert!(repo.exists(json!({"name": "bash"})).unwrap());
+	assert!(!repo.exists(json!({"name": "zsh"})).unwrap());
+
+	let patch = json!({"depends": ["glibc"], "reason": {"Dependency": {"of": "pacman"}}});
+	assert_eq!(repo.update(json!({"name": {"$ne": "glibc"}}), patch).unwrap(), 2);
+	assert!(matches!(repo.update(json!({}), json!({"unknown": 1})), Err(DbError::UnknownField { .. })));
+	assert_eq!(repo.find_one(json!({"name": "bash"})).unwrap().unwrap(), Pkg {
+		name: String::from("bash"),
+		depends: vec![String::from("glibc")],
+		reason: Reason::Dependency { of: String::from("pacman") },
+	});
+	assert_eq!(repo.count(json!({"depends": {"$contains": "glibc"}})).unwrap(), 2);
+
+	assert_eq!(repo.delete(json!({"reason": "Explicit"})).unwrap(), 1);
+	assert_eq!(repo.find_one(json!({"name": "glibc"})).unwrap(), None);
+	assert_eq!(repo.count(json!({})).unwrap(), 2);
+}
+
+#[test]
+fn transaction_rollback() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Pkg>().unwrap();
+	let pkg = |name: &str| Pkg { name: String::from(name), depends: Vec::new(), reason: Reason::Explicit };
+	assert_eq!(repo.insert_all([pkg("glibc"), pkg("bash")]).unwrap(), 2);
+
+	let res: anyhow::Result<()> = repo.transaction(|repo| {
+		repo.add(pkg("pacman")).unwrap();
+		// nested savepoint is committed with the outer one
+		repo.transaction(|repo| repo.delete(json!({"name": "bash"})))?;
+		assert_eq!(repo.count(json!({})).unwrap(), 2);
+		anyhow::bail!("abort")
+	});
+	assert!(res.is_err());
+	assert_eq!(repo.all().unwrap().into_iter().map(|it| it.name).collect::<Vec<_>>(), ["glibc", "bash"]);
+
+	assert_eq!(repo.add_all(vec![pkg("pacman")]).unwrap(), [pkg("pacman")]);
+	assert_eq!(repo.count(json!({})).unwrap(), 3);
+}
+
+#[test]
+fn query_builder() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Flag>().unwrap();
+	for (name, weight) in [("b", Some(2)), ("a", Some(2)), ("c", Some(1)), ("d", None)] {
+		repo.add(Flag { name: String::from(name), enabled: weight.is_some(), note: None, weight }).unwrap();
+	}
+	let names = |flags: Vec<Flag>| flags.into_iter().map(|it| it.name).collect::<Vec<_>>();
+	let sorted = repo.query().filter(json!({"enabled": true})).order_by("-weight").order_by("name").all().unwrap();
+	assert_eq!(names(sorted), ["a", "b", "c"]);
+	let page = repo.query().order_by("name").limit(2).offset(1).all().unwrap();
+	assert_eq!(names(page), ["b", "c"]);
+	let page = repo.query().order_by("name").offset(3).all().unwrap();
+	assert_eq!(names(page), ["d"]);
+
+	// same options in filter document
+	let query = repo.query().filter(json!({"enabled": true, "$sort": {"weight": -1, "name": 1}, "$limit": 2, "$fields": ["name", "enabled"]}));
+	assert_eq!(query.columns(), ["name", "enabled"]);
+	assert_eq!(query.values().unwrap(), [
+		json!({"name": "a", "enabled": true}).as_object().unwrap().clone(),
+		json!({"name": "b", "enabled": true}).as_object().unwrap().clone(),
+	]);
+	// keys of `$sort` object keep their order
+	let sorted = repo.find(json!({"enabled": true, "$sort": {"weight": -1, "name": -1}})).unwrap();
+	assert_eq!(names(sorted), ["b", "a", "c"]);
+	let sorted = repo.find(json!({"enabled": true, "$sort": {"name": -1, "weight": -1}})).unwrap();
+	assert_eq!(names(sorted), ["c", "b", "a"]);
+	let one = repo.query().filter(json!({"$sort": "-name"})).one().unwrap();
+	assert_eq!(one.map(|it| it.name), Some(String::from("d")));
+
+	assert!(matches!(repo.query().order_by("size").all(), Err(DbError::UnknownField { .. })));
+	assert!(matches!(repo.query().select(&["name"]).all(), Err(DbError::Schema(_))));
+	assert!(matches!(repo.find(json!({"$limit": -1})), Err(DbError::TypeMismatch { .. })));
+}
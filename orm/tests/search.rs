@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use orm::{db_init, DbError, TableOptions};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Doc {
+	repo: String,
+	name: String,
+	description: String,
+	groups: Vec<String>,
+}
+
+impl TableOptions for Doc {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["repo", "name"]]
+	}
+
+	fn search() -> &'static [(&'static str, f64)] {
+		&[("name", 10.0), ("description", 1.0), ("groups", 1.0)]
+	}
+}
+
+fn doc(repo: &str, name: &str, description: &str) -> Doc {
+	Doc { repo: String::from(repo), name: String::from(name), description: String::from(description), groups: Vec::new() }
+}
+
+#[test]
+fn ranked_search_with_filter() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<Doc>().unwrap();
+	repo.insert_all([
+		doc("extra", "zathura", "Minimalistic document viewer, supports PDF through plugins"),
+		doc("extra", "pdf", "Tools for PDF files"),
+		doc("community", "pdf", "Another pdf tool"),
+		doc("core", "glibc", "GNU C Library"),
+	]).unwrap();
+
+	let names = |query, filter| repo.search(query, filter, None).unwrap().into_iter().map(|it| (it.row.repo, it.row.name)).collect::<Vec<_>>();
+	// match of name comes first
+	assert_eq!(names("pdf", json!({"repo": "extra"})), [(String::from("extra"), String::from("pdf")), (String::from("extra"), String::from("zathura"))]);
+	assert_eq!(names("doc*", json!({})), [(String::from("extra"), String::from("zathura"))]);
+	assert!(names("pdf", json!({"repo": "core"})).is_empty());
+
+	let hits = repo.search("viewer", json!({}), Some(1)).unwrap();
+	assert_eq!(hits.len(), 1);
+	assert!(hits[0].snippet.contains("[viewer]"), "{}", hits[0].snippet);
+	assert!(hits[0].rank < 0.0);
+
+	// index follows changes of the table
+	repo.update(json!({"name": "glibc"}), json!({"groups": ["base"]})).unwrap();
+	assert_eq!(names("base", json!({})), [(String::from("core"), String::from("glibc"))]);
+	repo.delete(json!({"name": "zathura"})).unwrap();
+	assert!(names("viewer", json!({})).is_empty());
+	repo.reset().unwrap();
+	assert!(names("pdf", json!({})).is_empty());
+}
+
+mod plain {
+	use serde::{Deserialize, Serialize};
+
+	/// Same table as [super::Doc] without full-text index
+	#[derive(Serialize, Deserialize)]
+	pub struct Doc {
+		pub repo: String,
+		pub name: String,
+		pub description: String,
+		pub groups: Vec<String>,
+	}
+
+	impl orm::TableOptions for Doc {}
+}
+
+#[test]
+fn index_existing_rows() {
+	let db = db_init(None).unwrap();
+	let repo = db.get_repository::<plain::Doc>().unwrap();
+	assert!(matches!(repo.search("pdf", json!({}), None), Err(DbError::Schema(_))));
+	repo.add(plain::Doc { repo: String::from("extra"), name: String::from("pdf"), description: String::new(), groups: Vec::new() }).unwrap();
+
+	let repo = db.get_repository::<Doc>().unwrap();
+	assert_eq!(repo.search("pdf", json!({}), None).unwrap().len(), 1);
+}
@@ -0,0 +1,115 @@
+use std::env;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use crate::db::{DbHandler, Fnv1a, Migration, Table, TableOptions};
+use crate::wrapper::info::{InstalledPackage, list_installed, read_local_db};
+use crate::wrapper::repo::{list_to_db, Package, read_sync_dbs};
+
+/// Fingerprint of pacman database when the cache was built
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct CacheState {
+	pub fingerprint: String,
+	pub updated: i64,
+}
+
+impl TableOptions for CacheState {
+	fn migration() -> Migration {
+		Migration::Reset
+	}
+}
+
+/// `$XDG_CACHE_HOME/pacutil/pacutil.db`, fallback to `~/.cache`
+pub fn cache_path() -> Option<PathBuf> {
+	let dir = env::var_os("XDG_CACHE_HOME")
+		.filter(|it| !it.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| env::var_os("HOME").map(|it| PathBuf::from(it).join(".cache")))?;
+	Some(dir.join("pacutil").join("pacutil.db"))
+}
+
+fn modified(path: &Path) -> Option<(u128, u64)> {
+	let meta = fs::metadata(path).ok()?;
+	let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+	Some((mtime, meta.len()))
+}
+
+/// Path followed by mtime and size, written as bytes so the hash is the same for every Rust release
+fn hash_file(hasher: &mut Fnv1a, path: &Path) {
+	hasher.write(path.as_os_str().as_encoded_bytes());
+	hasher.write_u8(0);
+	match modified(path) {
+		Some((mtime, len)) => {
+			hasher.write(&mtime.to_le_bytes());
+			hasher.write(&len.to_le_bytes());
+		}
+		None => hasher.write_u8(0),
+	}
+}
+
+/// Hash of mtime and size of every `local/*/desc` and sync database in `repos`,
+/// along with schema of cached tables so the cache is rebuilt after their tables are reset
+pub fn fingerprint(dbpath: &Path, repos: &[String]) -> Result<String> {
+	let mut hasher = Fnv1a::default();
+	hasher.write(Table::<Package>::new()?.fingerprint().as_bytes());
+	hasher.write(Table::<InstalledPackage>::new()?.fingerprint().as_bytes());
+	let mut local = fs::read_dir(dbpath.join("local"))?
+		.filter_map(|it| it.ok())
+		.map(|it| it.path().join("desc"))
+		.collect::<Vec<_>>();
+	local.sort();
+	for desc in local {
+		hash_file(&mut hasher, &desc);
+	}
+	for repo in repos {
+		hasher.write(repo.as_bytes());
+		hasher.write_u8(0);
+	}
+	let mut sync = fs::read_dir(dbpath.join("sync"))?
+		.filter_map(|it| it.ok())
+		.map(|it| it.path())
+		.filter(|it| it.extension().map(|it| it == "db").unwrap_or_default())
+		.collect::<Vec<_>>();
+	sync.sort();
+	for path in sync {
+		hash_file(&mut hasher, &path);
+	}
+	Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Rebuild package tables if pacman database changed since last run or `refresh` is set.
+/// Cached tables use [Migration::Reset], rows dropped by a schema change are read from pacman database again here.
+pub fn load(db: &DbHandler, dbpath: &Path, repos: &[String], refresh: bool) -> Result<()> {
+	let fingerprint = fingerprint(dbpath, repos)?;
+	let state = db.get_repository::<CacheState>()?;
+	if !refresh && state.exists(json!({"fingerprint": {"$eq": fingerprint}}))? {
+		info!("Using cached package database");
+		return Ok(());
+	}
+
+	info!("Building package database");
+	let local = read_local_db(dbpath)?;
+	let sync = read_sync_dbs(dbpath, repos)?;
+	let packages = db.get_repository::<Package>()?;
+	let installed = db.get_repository::<InstalledPackage>()?;
+	// single transaction, otherwise every insert is synced to disk
+	packages.transaction(|packages| {
+		packages.reset()?;
+		installed.reset()?;
+		list_to_db(packages, sync, &local)?;
+		list_installed(&installed, local)?;
+		state.reset()?;
+		state.add(CacheState {
+			fingerprint,
+			updated: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64,
+		})?;
+		Ok(())
+	})
+}
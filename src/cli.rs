@@ -1,15 +1,124 @@
-use std::process::exit;
+use std::path::PathBuf;
 
-use clap::Parser;
-use serde_json::Value;
-use tracing::error;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[clap(version, about, long_about = None)]
-pub enum CommandLine {
+pub struct CommandLine {
+	/// print what would be done without running pacman
+	#[clap(long, global = true, value_parser)]
+	pub dry_run: bool,
+
+	/// output format
+	#[clap(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+	pub format: OutputFormat,
+
+	/// don't print column names in `table`, `plain` and `csv` output
+	#[clap(long, global = true, value_parser)]
+	pub no_header: bool,
+
+	/// pacman database directory
+	#[clap(long, global = true, value_parser, default_value = "/var/lib/pacman")]
+	pub dbpath: PathBuf,
+
+	/// pacman config, used to find order of repositories
+	#[clap(long, global = true, value_parser, default_value = "/etc/pacman.conf")]
+	pub config: PathBuf,
+
+	/// rebuild cached package database even if pacman database didn't change
+	#[clap(long, global = true, value_parser)]
+	pub refresh: bool,
+
+	/// use in-memory database instead of cache in `$XDG_CACHE_HOME/pacutil`
+	#[clap(long, global = true, value_parser)]
+	pub no_cache: bool,
+
+	#[clap(subcommand)]
+	pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+	/// Replace installed packages matched by filter with package from another repo
 	RemoveAll {
-		/// target folder to link to ramdisk
+		/// json filter of packages to replace e.g. `{"repo":"testing"}`
+		#[clap(value_parser)]
+		filter: String,
+	},
+	/// List packages installed as dependency that no explicitly installed package requires
+	Orphans {
+		/// remove orphans using `pacman -Rns`
+		#[clap(long, value_parser)]
+		remove: bool,
+		/// keep packages that are optional dependency of explicitly installed packages
+		#[clap(long, value_parser)]
+		keep_optional: bool,
+		/// don't ask for confirmation before removing
+		#[clap(long, value_parser)]
+		noconfirm: bool,
+	},
+	/// List installed packages that depend on a package, and packages that would break if it's removed
+	Rdeps {
+		/// installed package name
+		#[clap(value_parser)]
+		package: String,
+	},
+	/// List dependency cycles between installed packages
+	Cycles,
+	/// Run json filter against a table e.g. `query Packages '{"repo":"core"}'`
+	Query {
+		/// table to query, `Packages` or `InstalledPackages`
 		#[clap(value_parser)]
+		table: String,
+		/// json filter, every row when omitted, `$sort`, `$limit`, `$offset` and `$fields` keys work like options
+		#[clap(value_parser, default_value = "{}")]
 		filter: String,
-	}
-}
\ No newline at end of file
+		/// comma separated columns to print
+		#[clap(long, value_parser, value_delimiter = ',')]
+		fields: Vec<String>,
+		/// comma separated columns to sort by, prefix with `-` for descending order
+		#[clap(long, value_parser, value_delimiter = ',', allow_hyphen_values = true)]
+		sort: Vec<String>,
+		/// maximum number of rows
+		#[clap(long, value_parser)]
+		limit: Option<usize>,
+	},
+	/// Full-text search of sync packages by name, description, groups and provides, best match first
+	Search {
+		/// every term must match, `*` suffix matches prefix of a word e.g. `pdf*`
+		#[clap(value_parser, required = true)]
+		terms: Vec<String>,
+		/// json filter of packages e.g. `{"repo":"extra"}`
+		#[clap(long, value_parser, default_value = "{}")]
+		filter: String,
+		/// maximum number of packages
+		#[clap(long, value_parser)]
+		limit: Option<usize>,
+	},
+	/// Inspect or clear cached package database
+	Cache {
+		#[clap(subcommand)]
+		action: CacheCommand,
+	},
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+	/// Show location and state of the cache
+	Info,
+	/// Delete the cache, it will be rebuilt on next run
+	Clear,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+	/// columns aligned to terminal width
+	Table,
+	/// tab separated values
+	Plain,
+	Csv,
+	/// pretty printed array
+	Json,
+	/// one json object per line
+	Ndjson,
+}
@@ -1,8 +1,10 @@
-pub use connection::*;
+use std::path::Path;
 
-mod connection;
-/// TODO: move DIY orm to another crate
-mod field_type_extractor;
-mod field;
-mod table;
-mod util;
+pub use orm::*;
+
+/// [orm::db_init] with `PkgVersion` collation and `vercmp` function registered
+pub fn db_init(path: Option<&Path>) -> DbResult<DbHandler> {
+	let db = orm::db_init(path)?;
+	crate::version::register(&db)?;
+	Ok(db)
+}
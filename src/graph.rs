@@ -0,0 +1,449 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::version::PkgVersion;
+use crate::wrapper::info::InstalledPackage;
+use crate::wrapper::repo::Package;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DepMod {
+	Eq,
+	Ge,
+	Le,
+	Gt,
+	Lt,
+}
+
+impl DepMod {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			DepMod::Eq => "=",
+			DepMod::Ge => ">=",
+			DepMod::Le => "<=",
+			DepMod::Gt => ">",
+			DepMod::Lt => "<",
+		}
+	}
+}
+
+/// Dependency in pacman format `name[<op>version][: description]`,
+/// also used for provides e.g. `sh` or `libfoo.so=1-64`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Depend {
+	pub name: String,
+	pub constraint: Option<(DepMod, PkgVersion)>,
+}
+
+impl FromStr for Depend {
+	type Err = std::convert::Infallible;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		// optional dependency have description after `: `
+		let s = s.split_once(": ").map(|it| it.0).unwrap_or(s).trim();
+		let Some(idx) = s.find(['<', '>', '=']) else {
+			return Ok(Self { name: s.to_string(), constraint: None });
+		};
+		let (name, rest) = s.split_at(idx);
+		let (op, version) = [DepMod::Ge, DepMod::Le, DepMod::Eq, DepMod::Gt, DepMod::Lt].into_iter()
+			.find_map(|op| rest.strip_prefix(op.as_str()).map(|version| (op, version)))
+			.unwrap_or((DepMod::Eq, rest));
+		Ok(Self { name: name.to_string(), constraint: Some((op, PkgVersion::from(version))) })
+	}
+}
+
+impl Display for Depend {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.name)?;
+		if let Some((op, version)) = &self.constraint {
+			f.write_str(op.as_str())?;
+			Display::fmt(version, f)?;
+		}
+		Ok(())
+	}
+}
+
+impl Depend {
+	pub fn parse_all(depends: &[String]) -> Vec<Depend> {
+		depends.iter().filter_map(|it| it.parse().ok()).collect()
+	}
+
+	fn version_match(&self, version: &PkgVersion) -> bool {
+		match &self.constraint {
+			None => true,
+			Some((op, target)) => {
+				let ord = version.cmp(target);
+				match op {
+					DepMod::Eq => ord.is_eq(),
+					DepMod::Ge => ord.is_ge(),
+					DepMod::Le => ord.is_le(),
+					DepMod::Gt => ord.is_gt(),
+					DepMod::Lt => ord.is_lt(),
+				}
+			}
+		}
+	}
+
+	/// Same rule as `_alpm_depcmp`, unversioned provision can't satisfy versioned dependency
+	pub fn satisfied_by(&self, name: &str, version: &PkgVersion, provides: &[Depend]) -> bool {
+		if self.name == name && self.version_match(version) {
+			return true;
+		}
+		provides.iter()
+			.filter(|it| it.name == self.name)
+			.any(|it| match (&self.constraint, &it.constraint) {
+				(None, _) => true,
+				(Some(_), None) => false,
+				(Some(_), Some((_, version))) => self.version_match(version),
+			})
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+	pub name: String,
+	pub version: PkgVersion,
+	pub depends: Vec<Depend>,
+	pub optdepends: Vec<Depend>,
+	pub provides: Vec<Depend>,
+}
+
+impl Node {
+	fn satisfies(&self, depend: &Depend) -> bool {
+		depend.satisfied_by(&self.name, &self.version, &self.provides)
+	}
+}
+
+impl From<&InstalledPackage> for Node {
+	fn from(pkg: &InstalledPackage) -> Self {
+		Self {
+			name: pkg.name.clone(),
+			version: PkgVersion::from(pkg.installed.as_str()),
+			depends: Depend::parse_all(&pkg.depends),
+			optdepends: Depend::parse_all(&pkg.optdepends),
+			provides: Depend::parse_all(&pkg.provides),
+		}
+	}
+}
+
+/// Sync package, e.g. alternative that may replace installed package
+impl From<&Package> for Node {
+	fn from(pkg: &Package) -> Self {
+		Self {
+			name: pkg.name.clone(),
+			version: pkg.version.clone(),
+			depends: Depend::parse_all(&pkg.depends),
+			optdepends: Depend::parse_all(&pkg.optdepends),
+			provides: Depend::parse_all(&pkg.provides),
+		}
+	}
+}
+
+/// Dependency graph of installed packages, edges are resolved through provides
+#[derive(Debug, Default)]
+pub struct DepGraph {
+	nodes: Vec<Node>,
+	index: HashMap<String, usize>,
+	/// package or virtual package name to nodes that may satisfy it
+	providers: HashMap<String, Vec<usize>>,
+}
+
+impl FromIterator<Node> for DepGraph {
+	fn from_iter<I: IntoIterator<Item=Node>>(iter: I) -> Self {
+		let mut graph = DepGraph::default();
+		for node in iter {
+			graph.add(node);
+		}
+		graph
+	}
+}
+
+impl DepGraph {
+	pub fn add(&mut self, node: Node) {
+		let idx = self.nodes.len();
+		self.index.insert(node.name.clone(), idx);
+		self.providers.entry(node.name.clone()).or_default().push(idx);
+		for provide in &node.provides {
+			self.providers.entry(provide.name.clone()).or_default().push(idx);
+		}
+		self.nodes.push(node);
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Node> {
+		self.index.get(name).map(|&it| &self.nodes[it])
+	}
+
+	/// Nodes that can satisfy the dependency, ignoring nodes in `removed`
+	fn satisfiers<'a>(&'a self, depend: &'a Depend, removed: &'a HashSet<usize>) -> impl Iterator<Item=usize> + 'a {
+		self.providers.get(&depend.name).into_iter().flatten().copied()
+			.filter(move |it| !removed.contains(it) && self.nodes[*it].satisfies(depend))
+	}
+
+	/// Every node satisfying a dependency of `idx`, like pacman a package is needed by each of its providers
+	fn edges(&self, idx: usize, optional: bool) -> impl Iterator<Item=usize> + '_ {
+		let node = &self.nodes[idx];
+		let optdepends = if optional { node.optdepends.as_slice() } else { &[] };
+		node.depends.iter().chain(optdepends)
+			.flat_map(move |it| self.providers.get(&it.name).into_iter().flatten().copied().filter(move |&p| self.nodes[p].satisfies(it)))
+	}
+
+	/// Packages that depend on `name` directly
+	pub fn required_by(&self, name: &str) -> Vec<&str> {
+		self.dependents(name, |node| &node.depends)
+	}
+
+	/// Packages that optionally depend on `name` directly
+	pub fn optional_for(&self, name: &str) -> Vec<&str> {
+		self.dependents(name, |node| &node.optdepends)
+	}
+
+	fn dependents(&self, name: &str, depends: impl Fn(&Node) -> &[Depend]) -> Vec<&str> {
+		let Some(target) = self.get(name) else { return Vec::new(); };
+		self.nodes.iter()
+			.filter(|node| depends(node).iter().any(|it| target.satisfies(it)))
+			.map(|it| it.name.as_str())
+			.collect()
+	}
+
+	/// Every package needed by `roots` transitively including the roots,
+	/// `optional` also follow optional dependencies
+	pub fn closure<'a>(&self, roots: impl IntoIterator<Item=&'a str>, optional: bool) -> HashSet<&str> {
+		let mut visited = HashSet::new();
+		let mut queue = roots.into_iter().filter_map(|it| self.index.get(it).copied()).collect::<VecDeque<_>>();
+		while let Some(idx) = queue.pop_front() {
+			if visited.insert(idx) {
+				queue.extend(self.edges(idx, optional));
+			}
+		}
+		visited.into_iter().map(|it| self.nodes[it].name.as_str()).collect()
+	}
+
+	/// Dependency cycles, each cycle is a strongly connected component
+	pub fn cycles(&self) -> Vec<Vec<&str>> {
+		// iterative tarjan
+		let len = self.nodes.len();
+		let mut index = vec![usize::MAX; len];
+		let mut low = vec![0; len];
+		let mut on_stack = vec![false; len];
+		let mut stack = Vec::new();
+		let mut counter = 0;
+		let mut cycles = Vec::new();
+		for root in 0..len {
+			if index[root] != usize::MAX {
+				continue;
+			}
+			let mut call = vec![(root, self.edges(root, false).collect::<Vec<_>>(), 0)];
+			index[root] = counter;
+			low[root] = counter;
+			counter += 1;
+			stack.push(root);
+			on_stack[root] = true;
+			while let Some((v, edges, i)) = call.last_mut() {
+				let v = *v;
+				if let Some(&w) = edges.get(*i) {
+					*i += 1;
+					if index[w] == usize::MAX {
+						index[w] = counter;
+						low[w] = counter;
+						counter += 1;
+						stack.push(w);
+						on_stack[w] = true;
+						call.push((w, self.edges(w, false).collect(), 0));
+					} else if on_stack[w] {
+						low[v] = low[v].min(index[w]);
+					}
+					continue;
+				}
+				call.pop();
+				if let Some((parent, _, _)) = call.last() {
+					low[*parent] = low[*parent].min(low[v]);
+				}
+				if low[v] == index[v] {
+					let mut component = Vec::new();
+					while let Some(w) = stack.pop() {
+						on_stack[w] = false;
+						component.push(w);
+						if w == v { break; }
+					}
+					let self_loop = self.edges(v, false).any(|it| it == v);
+					if component.len() > 1 || self_loop {
+						cycles.push(component.into_iter().rev().map(|it| self.nodes[it].name.as_str()).collect());
+					}
+				}
+			}
+		}
+		cycles
+	}
+
+	/// Packages left with unsatisfied dependency if `names` are removed,
+	/// including packages broken by removal of other broken packages.
+	pub fn breaks_if_removed<'a>(&self, names: impl IntoIterator<Item=&'a str>) -> Vec<&str> {
+		let mut removed = names.into_iter().filter_map(|it| self.index.get(it).copied()).collect::<HashSet<_>>();
+		let mut broken = Vec::new();
+		loop {
+			let newly = (0..self.nodes.len())
+				.filter(|it| !removed.contains(it))
+				.filter(|&it| self.nodes[it].depends.iter().any(|dep| {
+					self.satisfiers(dep, &HashSet::new()).next().is_some() && self.satisfiers(dep, &removed).next().is_none()
+				}))
+				.collect::<Vec<_>>();
+			if newly.is_empty() {
+				break;
+			}
+			removed.extend(&newly);
+			broken.extend(newly.into_iter().map(|it| self.nodes[it].name.as_str()));
+		}
+		broken
+	}
+
+	/// Replacements refused because dependencies would be left unsatisfied after every replacement is applied together,
+	/// with each unsatisfied dependency as `(dependent, depend)`.
+	/// Refused replacements are dropped and the rest is checked again until nothing more is refused.
+	/// Broken dependencies of the installed packages and new dependencies on packages that aren't installed are ignored.
+	pub fn refused_replacements(&self, replacements: &[Node]) -> HashMap<String, Vec<(String, Depend)>> {
+		let mut refused: HashMap<String, Vec<(String, Depend)>> = HashMap::new();
+		loop {
+			let accepted = replacements.iter()
+				.filter(|it| !refused.contains_key(&it.name))
+				.map(|it| (it.name.as_str(), it))
+				.collect::<HashMap<_, _>>();
+			let after = self.nodes.iter()
+				.map(|it| accepted.get(it.name.as_str()).copied().unwrap_or(it).clone())
+				.collect::<DepGraph>();
+			let none = HashSet::new();
+			let mut newly: HashMap<String, Vec<(String, Depend)>> = HashMap::new();
+			for node in &after.nodes {
+				for dep in node.depends.iter().filter(|dep| after.satisfiers(dep, &none).next().is_none()) {
+					let blamed = if accepted.contains_key(node.name.as_str()) {
+						// new dependency of the replacement on installed package with wrong version
+						if after.providers.contains_key(&dep.name) { vec![node.name.as_str()] } else { Vec::new() }
+					} else {
+						// replaced packages that satisfied the dependency before
+						self.satisfiers(dep, &none)
+							.map(|it| self.nodes[it].name.as_str())
+							.filter(|it| accepted.contains_key(it))
+							.collect()
+					};
+					for name in blamed {
+						newly.entry(name.to_string()).or_default().push((node.name.clone(), dep.clone()));
+					}
+				}
+			}
+			if newly.is_empty() {
+				return refused;
+			}
+			refused.extend(newly);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::version::PkgVersion;
+
+	use super::{Depend, DepGraph, DepMod, Node};
+
+	fn node(name: &str, version: &str, depends: &[&str], optdepends: &[&str], provides: &[&str]) -> Node {
+		let parse = |it: &[&str]| it.iter().map(|it| it.parse().unwrap()).collect();
+		Node {
+			name: name.to_string(),
+			version: PkgVersion::from(version),
+			depends: parse(depends),
+			optdepends: parse(optdepends),
+			provides: parse(provides),
+		}
+	}
+
+	fn graph() -> DepGraph {
+		[
+			node("glibc", "2.36-6", &[], &[], &[]),
+			node("bash", "5.1-1", &["glibc", "readline>=8.0"], &[], &["sh"]),
+			node("readline", "8.2-1", &["glibc", "ncurses"], &[], &[]),
+			node("ncurses", "6.3-3", &["glibc"], &[], &[]),
+			node("pacman", "6.0.2-6", &["bash", "glibc>=2.35", "libarchive"], &["perl-locale-gettext: translation"], &[]),
+			node("libarchive", "3.6.1-2", &["glibc", "sh"], &[], &[]),
+			node("perl-locale-gettext", "1.07-1", &[], &[], &[]),
+			// a <-> b
+			node("a", "1", &["b"], &[], &[]),
+			node("b", "1", &["a=1"], &[], &[]),
+		].into_iter().collect()
+	}
+
+	#[test]
+	fn parse_depend() {
+		let dep: Depend = "glibc>=2.35".parse().unwrap();
+		assert_eq!(dep.name, "glibc");
+		assert_eq!(dep.constraint, Some((DepMod::Ge, PkgVersion::from("2.35"))));
+		let dep: Depend = "perl-locale-gettext: translation support".parse().unwrap();
+		assert_eq!(dep, Depend { name: String::from("perl-locale-gettext"), constraint: None });
+		assert_eq!("libfoo.so=1-64".parse::<Depend>().unwrap().to_string(), "libfoo.so=1-64");
+	}
+
+	#[test]
+	fn satisfy_through_provides() {
+		let provides = ["sh", "java-runtime=17"].map(|it| it.parse::<Depend>().unwrap());
+		let version = PkgVersion::from("1.0-1");
+		assert!("sh".parse::<Depend>().unwrap().satisfied_by("bash", &version, &provides));
+		assert!("java-runtime>=11".parse::<Depend>().unwrap().satisfied_by("jre", &version, &provides));
+		assert!(!"sh>=1".parse::<Depend>().unwrap().satisfied_by("bash", &version, &provides));
+		assert!(!"bash>1.0".parse::<Depend>().unwrap().satisfied_by("bash", &version, &provides));
+	}
+
+	#[test]
+	fn queries() {
+		let graph = graph();
+		let mut required_by = graph.required_by("bash");
+		required_by.sort();
+		assert_eq!(required_by, ["libarchive", "pacman"]);
+		assert_eq!(graph.optional_for("perl-locale-gettext"), ["pacman"]);
+
+		let closure = graph.closure(["pacman"], false);
+		assert_eq!(closure.len(), 6);
+		assert!(!closure.contains("perl-locale-gettext"));
+		assert!(graph.closure(["pacman"], true).contains("perl-locale-gettext"));
+
+		assert_eq!(graph.cycles(), [vec!["a", "b"]]);
+
+		let mut broken = graph.breaks_if_removed(["ncurses"]);
+		broken.sort();
+		assert_eq!(broken, ["bash", "libarchive", "pacman", "readline"]);
+	}
+
+	#[test]
+	fn every_provider_is_needed() {
+		let mut graph = graph();
+		graph.add(node("dash", "0.5-1", &[], &[], &["sh"]));
+		let closure = graph.closure(["libarchive"], false);
+		assert!(closure.contains("bash") && closure.contains("dash"));
+	}
+
+	#[test]
+	fn replacements() {
+		let graph = graph();
+		assert!(graph.refused_replacements(&[node("glibc", "2.37-1", &[], &[], &[])]).is_empty());
+		let refused = graph.refused_replacements(&[node("glibc", "2.34-1", &[], &[], &[])]);
+		assert_eq!(refused["glibc"], [(String::from("pacman"), "glibc>=2.35".parse().unwrap())]);
+
+		// ncurses needs the glibc that was refused
+		let refused = graph.refused_replacements(&[
+			node("glibc", "2.34-1", &[], &[], &[]),
+			node("ncurses", "6.2-1", &["glibc=2.34-1"], &[], &[]),
+		]);
+		assert_eq!(refused.len(), 2);
+		assert_eq!(refused["ncurses"], [(String::from("ncurses"), "glibc=2.34-1".parse().unwrap())]);
+	}
+
+	#[test]
+	fn replace_together() {
+		// both moved from testing back to core
+		let graph: DepGraph = [
+			node("foo", "2.0-1", &["foo-libs=2.0-1"], &[], &[]),
+			node("foo-libs", "2.0-1", &[], &[], &[]),
+		].into_iter().collect();
+		let foo = node("foo", "1.0-1", &["foo-libs=1.0-1"], &[], &[]);
+		let foo_libs = node("foo-libs", "1.0-1", &[], &[], &[]);
+		assert!(graph.refused_replacements(&[foo.clone(), foo_libs.clone()]).is_empty());
+		assert_eq!(graph.refused_replacements(std::slice::from_ref(&foo_libs)).keys().collect::<Vec<_>>(), ["foo-libs"]);
+		assert_eq!(graph.refused_replacements(&[foo]).keys().collect::<Vec<_>>(), ["foo"]);
+	}
+}
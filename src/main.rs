@@ -1,68 +1,399 @@
-use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
+use std::fs;
+use std::io::{stderr, stdin, Write};
+use std::path::Path;
 
+use anyhow::bail;
 use clap::Parser;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, warn};
 
-use crate::cli::CommandLine;
-use crate::db::db_init;
-use crate::util::{group, parse_json, split};
-use crate::wrapper::info::{InstalledPackage, list_installed};
-use crate::wrapper::repo::{list_to_db, Package};
+use crate::cache::{cache_path, CacheState};
+use crate::cli::{CacheCommand, Command, CommandLine};
+use crate::db::{db_init, DbHandler};
+use crate::util::{human_size, parse_json};
+use crate::version::PkgVersion;
+use crate::graph::{DepGraph, Node};
+use crate::output::Output;
+use crate::query::{query, QueryOptions};
+use crate::search::search;
+use crate::wrapper::info::{InstalledPackage, read_local_db};
+use crate::wrapper::install::execute;
+use crate::wrapper::plan::{Action, Plan, PlanEntry};
+use crate::wrapper::repo::{Package, repos_from_config};
 
 mod wrapper;
 mod util;
 mod db;
-mod ser;
 mod cli;
+mod version;
+mod graph;
+mod cache;
+mod output;
+mod query;
+mod search;
 
 fn main() -> anyhow::Result<()> {
-	tracing_subscriber::fmt::init();
+	// keep stdout for results
+	tracing_subscriber::fmt().with_writer(stderr).init();
 	let arg = CommandLine::parse();
+	let output = Output::new(&arg);
+	// database is kept to mark entries of the plan as done
+	let (plan, noconfirm, db) = match &arg.command {
+		Command::RemoveAll { filter } => {
+			let repos = sync_repos(&arg.config);
+			let db = open_db(&arg, &repos)?;
+			(remove_all(&db, filter)?, true, Some(db))
+		}
+		Command::Orphans { remove, keep_optional, noconfirm } => {
+			let plan = orphans(&arg.dbpath, *keep_optional)?;
+			if !remove {
+				return plan.print(&output);
+			}
+			(plan, *noconfirm, None)
+		}
+		Command::Rdeps { package } => {
+			return output.print(&["relation", "package"], &rdeps(&arg.dbpath, package)?);
+		}
+		Command::Cycles => {
+			return output.print(&["size", "packages"], &cycles(&arg.dbpath)?);
+		}
+		Command::Query { table, filter, fields, sort, limit } => {
+			let db = open_db(&arg, &sync_repos(&arg.config))?;
+			let options = QueryOptions { fields: fields.clone(), sort: sort.clone(), limit: *limit };
+			let result = query(&db, table, parse_json(filter), &options)?;
+			return output.print(&result.columns, &result.rows);
+		}
+		Command::Search { terms, filter, limit } => {
+			let db = open_db(&arg, &sync_repos(&arg.config))?;
+			let result = search(&db, terms, parse_json(filter), *limit)?;
+			return output.print(&search::COLUMNS, &result);
+		}
+		Command::Cache { action } => {
+			return cache_command(&arg, &output, action);
+		}
+	};
+	if plan.is_empty() {
+		info!("Nothing to do");
+		return Ok(());
+	}
+	plan.print(&output)?;
+	if arg.dry_run || !(plan.has(Action::Replace) || plan.has(Action::Remove)) {
+		return Ok(());
+	}
+	if plan.has(Action::Remove) && !noconfirm && !confirm(&format!("Remove {} packages?", plan.len()))? {
+		return Ok(());
+	}
+	execute(&plan, db.as_ref())
+}
 
-	//exec().expect("1");
-	/*for x in list().unwrap().into_iter().map(|it| it.to_object()) {
-		println!("{:?}", x);
-	}*/
-//	list_to_db(&repo)?;
-	//println!("{:?}", list_installed(&repo)?);
-	//println!("{:?}", repo.all());
-
-	//println!("{:?}", repo.all());
-	/*
-		for x in list_installed_with_detail() {
-			if let Multiple(arr) = x {
-				if arr.iter().any(|it| obj_match(&json!({"repo":"cachyos*"}), it)) {
-					let pkg = arr.into_iter().find(|it| !obj_match(&json!({"repo":"cachyos*"}), it))
-						.and_then(|it| from_value::<InstallablePackage>(it).ok());
-					if let Some(pkg) = pkg {
-						install_pkg(&pkg)?;
-					}
+fn confirm(question: &str) -> anyhow::Result<bool> {
+	// stdout only carries the plan, so it stays parsable in json and csv
+	let mut err = stderr();
+	write!(err, "{question} [y/N] ")?;
+	err.flush()?;
+	let mut answer = String::new();
+	stdin().read_line(&mut answer)?;
+	Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Packages installed as dependency which is not required by any explicitly installed package
+fn orphans(dbpath: &Path, keep_optional: bool) -> anyhow::Result<Plan> {
+	let plan = orphans_of(&read_local_db(dbpath)?, keep_optional);
+	info!("Found {} orphans", plan.len());
+	Ok(plan)
+}
+
+fn orphans_of(local: &[InstalledPackage], keep_optional: bool) -> Plan {
+	let graph = local.iter().map(Node::from).collect::<DepGraph>();
+	let explicit = local.iter().filter(|it| !it.as_dependency).map(|it| it.name.as_str());
+	let needed = graph.closure(explicit, keep_optional);
+
+	let mut plan = Plan::default();
+	for pkg in local.iter().filter(|it| it.as_dependency && !needed.contains(it.name.as_str())) {
+		plan.push(PlanEntry {
+			name: pkg.name.clone(),
+			from_repo: None,
+			from_version: Some(PkgVersion::from(pkg.installed.as_str())),
+			to_repo: None,
+			to_version: None,
+			as_dependency: true,
+			size: pkg.size,
+			action: Action::Remove,
+		});
+	}
+	plan.entries.sort_by(|a, b| a.name.cmp(&b.name));
+	plan
+}
+
+#[derive(Serialize)]
+struct Relation {
+	/// `required by`, `optional for` or `breaks if removed`
+	relation: &'static str,
+	package: String,
+}
+
+/// Installed packages related to `name` through dependencies
+fn rdeps(dbpath: &Path, name: &str) -> anyhow::Result<Vec<Relation>> {
+	let local = read_local_db(dbpath)?;
+	let graph = local.iter().map(Node::from).collect::<DepGraph>();
+	if graph.get(name).is_none() {
+		bail!("Package `{name}` is not installed");
+	}
+	let relations = [
+		("required by", graph.required_by(name)),
+		("optional for", graph.optional_for(name)),
+		("breaks if removed", graph.breaks_if_removed([name])),
+	];
+	let mut res = Vec::new();
+	for (relation, mut packages) in relations {
+		packages.sort();
+		res.extend(packages.into_iter().map(|it| Relation { relation, package: it.to_string() }));
+	}
+	Ok(res)
+}
+
+#[derive(Serialize)]
+struct Cycle {
+	size: usize,
+	/// space separated package names
+	packages: String,
+}
+
+/// Dependency cycles of installed packages, largest first
+fn cycles(dbpath: &Path) -> anyhow::Result<Vec<Cycle>> {
+	let local = read_local_db(dbpath)?;
+	let graph = local.iter().map(Node::from).collect::<DepGraph>();
+	let mut res = graph.cycles().into_iter()
+		.map(|mut it| {
+			it.sort();
+			Cycle { size: it.len(), packages: it.join(" ") }
+		})
+		.collect::<Vec<_>>();
+	res.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.packages.cmp(&b.packages)));
+	info!("Found {} dependency cycles", res.len());
+	Ok(res)
+}
+
+/// Open cached database and rebuild it if needed
+fn open_db(arg: &CommandLine, repos: &[String]) -> anyhow::Result<DbHandler> {
+	let path = if arg.no_cache { None } else { cache_path() };
+	let db = db_init(path.as_deref())?;
+	cache::load(&db, &arg.dbpath, repos, arg.refresh)?;
+	Ok(db)
+}
+
+#[derive(Serialize, Default)]
+struct CacheInfo {
+	path: String,
+	size: Option<String>,
+	state: &'static str,
+	fingerprint: Option<String>,
+	updated: Option<i64>,
+	packages: Option<u64>,
+	installed: Option<u64>,
+}
+
+fn cache_command(arg: &CommandLine, output: &Output, action: &CacheCommand) -> anyhow::Result<()> {
+	let Some(path) = cache_path() else {
+		bail!("Can't find cache directory, set `XDG_CACHE_HOME` or `HOME`");
+	};
+	match action {
+		CacheCommand::Info => {
+			let mut info = CacheInfo { path: path.display().to_string(), state: "empty", ..Default::default() };
+			if path.exists() {
+				info.size = Some(human_size(fs::metadata(&path)?.len() as i64));
+				let db = db_init(Some(&path))?;
+				let state = db.get_repository::<CacheState>()?.find_one(json!({}))?;
+				let fresh = state.as_ref()
+					.map(|it| cache::fingerprint(&arg.dbpath, &sync_repos(&arg.config)).map(|f| f == it.fingerprint).unwrap_or_default())
+					.unwrap_or_default();
+				info.state = if fresh { "fresh" } else { "stale" };
+				if let Some(state) = state {
+					info.fingerprint = Some(state.fingerprint);
+					info.updated = Some(state.updated);
+				}
+				info.packages = Some(db.get_repository::<Package>()?.count(json!({}))?);
+				info.installed = Some(db.get_repository::<InstalledPackage>()?.count(json!({}))?);
+			}
+			let columns = ["path", "size", "state", "fingerprint", "updated", "packages", "installed"];
+			output.print(&columns, &[info])?;
+		}
+		CacheCommand::Clear => {
+			for suffix in ["", "-wal", "-shm", "-journal"] {
+				let mut file = path.clone().into_os_string();
+				file.push(suffix);
+				if Path::new(&file).exists() {
+					fs::remove_file(&file)?;
 				}
 			}
-		}*/
-	match arg {
-		CommandLine::RemoveAll { filter } => {
-			remove_all(filter)
+			info!("Removed `{}`", path.display());
 		}
 	}
+	Ok(())
 }
 
-fn remove_all(filter: String) -> anyhow::Result<()> {
-	let db = db_init()?;
-
-	let repo = db.get_repository::<Package>();
-	list_to_db(&repo)?;
-	let json = parse_json(&filter);
-	let packages = repo.find(json);
-	println!("{:?}", packages);
-	let (matched, available) = split(packages, |it| it.installed.as_ref().map(|i| i == &it.version).unwrap_or_default());
-	let mut alternatives = group(available, |it| it.name.clone());
-
-	let installed = db.get_repository::<InstalledPackage>();
-	list_installed(&installed)?;
-	let installed = group(installed.take_all(), |it| it.name.clone()).into_iter().map(|it| (it.0, it.1.into_iter().next().unwrap()));
-	let installed: HashMap<_, _, RandomState> = HashMap::from_iter(installed);
-	println!("{:?}", installed);
-todo!("Replace package from matched with another");
-	Ok(())
-}
\ No newline at end of file
+/// Repositories from pacman config, fallback to every sync database if config is unreadable
+fn sync_repos(config: &Path) -> Vec<String> {
+	repos_from_config(config).unwrap_or_else(|err| {
+		warn!("{:#}, reading every sync database instead", err);
+		Vec::new()
+	})
+}
+
+fn remove_all(db: &DbHandler, filter: &str) -> anyhow::Result<Plan> {
+	let local = db.get_repository::<InstalledPackage>()?.all()?;
+	let graph = local.iter().map(Node::from).collect::<DepGraph>();
+	let installed: HashMap<&str, &InstalledPackage> = local.iter().map(|it| (it.name.as_str(), it)).collect();
+
+	let json = parse_json(filter);
+	// alternatives are packages with the same name not matched by filter,
+	// rows are kept in `pacman.conf` order so first alternative is from repo with highest priority
+	let rows = db.join::<Package, Package>("name")?
+		.filter(json.clone())
+		.filter_right(json!({"$not": json}))
+		.left()?;
+	let mut alternatives: Vec<(Package, Option<Package>)> = Vec::new();
+	for (pkg, alt) in rows {
+		// only packages that currently installed from matched repo need to be replaced
+		if pkg.installed.as_ref() != Some(&pkg.version) {
+			continue;
+		}
+		match alternatives.last() {
+			Some((last, _)) if last.repo == pkg.repo && last.name == pkg.name => {}
+			_ => alternatives.push((pkg, alt)),
+		}
+	}
+
+	// replacements are checked together, so packages depending on each other can be replaced at once
+	let candidates = alternatives.iter().filter_map(|(_, alt)| alt.as_ref()).map(Node::from).collect::<Vec<_>>();
+	let refusals = graph.refused_replacements(&candidates);
+
+	let mut plan = Plan::default();
+	let mut missing = Vec::new();
+	let mut refused = 0;
+	for (pkg, alt) in alternatives {
+		let as_dependency = installed.get(pkg.name.as_str()).map(|it| it.as_dependency).unwrap_or_default();
+		let Some(alt) = alt else {
+			missing.push(PlanEntry {
+				name: pkg.name,
+				from_repo: Some(pkg.repo),
+				from_version: Some(pkg.version),
+				to_repo: None,
+				to_version: None,
+				as_dependency,
+				size: pkg.isize,
+				action: Action::Skip,
+			});
+			continue;
+		};
+		if let Some(unsatisfied) = refusals.get(&pkg.name) {
+			for (dependent, depend) in unsatisfied {
+				warn!("Refusing to replace {}/{} with {}/{} {}: {} requires {}", pkg.repo, pkg.name, alt.repo, alt.name, alt.version, dependent, depend);
+			}
+			refused += 1;
+			continue;
+		}
+		plan.push(PlanEntry {
+			name: pkg.name.clone(),
+			to_repo: Some(alt.repo.clone()),
+			to_version: Some(alt.version.clone()),
+			as_dependency,
+			size: alt.isize,
+			action: Action::Replace,
+			from_repo: Some(pkg.repo),
+			from_version: Some(pkg.version),
+		});
+	}
+
+	info!("Replacing {} packages ({} without alternative, {} refused)", plan.len(), missing.len(), refused);
+	// packages without alternative are reported after the replacements
+	for entry in missing {
+		plan.push(entry);
+	}
+	Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::{Path, PathBuf};
+
+	use serde_json::json;
+
+	use crate::db::{db_init, DbHandler};
+	use crate::version::PkgVersion;
+	use crate::wrapper::info::{InstalledPackage, read_local_db};
+	use crate::wrapper::plan::{Action, Plan};
+	use crate::wrapper::repo::Package;
+
+	use super::{orphans, orphans_of, remove_all};
+
+	fn dbpath() -> PathBuf {
+		Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/db")
+	}
+
+	/// Fixture database with repositories in order of `tests/fixtures/pacman.conf`
+	fn fixture_db() -> DbHandler {
+		let db = db_init(None).unwrap();
+		let repos = ["testing", "core", "extra", "community"].map(String::from);
+		crate::cache::load(&db, &dbpath(), &repos, false).unwrap();
+		db
+	}
+
+	fn names(plan: &Plan) -> Vec<&str> {
+		plan.entries.iter().map(|it| it.name.as_str()).collect()
+	}
+
+	#[test]
+	fn orphans_of_fixture() {
+		// glibc is required by pacman, perl-locale-gettext is only optional
+		let plan = orphans(&dbpath(), false).unwrap();
+		assert_eq!(names(&plan), ["perl-locale-gettext"]);
+		assert!(orphans(&dbpath(), true).unwrap().is_empty());
+	}
+
+	#[test]
+	fn orphans_with_two_providers() {
+		let mut local = read_local_db(&dbpath()).unwrap();
+		// pacman depends on `bash`, both providers are needed no matter which is read first
+		for name in ["bash", "bash-compat"] {
+			local.push(InstalledPackage {
+				name: format!("{name}-provider"),
+				installed: String::from("1.0-1"),
+				as_dependency: true,
+				provides: vec![String::from("bash=5.1")],
+				..Default::default()
+			});
+		}
+		assert_eq!(names(&orphans_of(&local, false)), ["perl-locale-gettext"]);
+		local.reverse();
+		assert_eq!(names(&orphans_of(&local, false)), ["perl-locale-gettext"]);
+	}
+
+	#[test]
+	fn replace_from_testing() {
+		let db = fixture_db();
+		// pretend glibc was installed from testing
+		let version = json!("2.37-1");
+		db.get_repository::<Package>().unwrap().update(json!({"name": "glibc"}), json!({"installed": version})).unwrap();
+		db.get_repository::<InstalledPackage>().unwrap().update(json!({"name": "glibc"}), json!({"installed": version})).unwrap();
+
+		let plan = remove_all(&db, r#"{"repo":"testing"}"#).unwrap();
+		assert_eq!(names(&plan), ["glibc"]);
+		let entry = &plan.entries[0];
+		assert_eq!((entry.from_repo.as_deref(), entry.to_repo.as_deref()), (Some("testing"), Some("core")));
+		assert_eq!(entry.to_version, Some(PkgVersion::from("2.36-6")));
+		assert!(entry.as_dependency);
+	}
+
+	#[test]
+	fn report_without_alternative() {
+		// pacman is only in core
+		let plan = remove_all(&fixture_db(), r#"{"repo":"core"}"#).unwrap();
+		assert_eq!(names(&plan), ["glibc", "pacman"]);
+		assert_eq!((plan.entries[0].action, plan.entries[0].to_repo.as_deref()), (Action::Replace, Some("testing")));
+		assert_eq!((plan.entries[1].action, plan.entries[1].to_repo.as_deref()), (Action::Skip, None));
+		assert_eq!(plan.entries[1].from_version, Some(PkgVersion::from("6.0.2-6")));
+	}
+}
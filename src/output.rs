@@ -0,0 +1,189 @@
+use std::env;
+use std::io::{stdout, BufWriter, Write};
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::cli::{CommandLine, OutputFormat};
+
+/// Shared printer for every subcommand, records are converted with their [Serialize] impl
+pub struct Output {
+	pub format: OutputFormat,
+	/// print column names for `table`, `plain` and `csv`
+	pub header: bool,
+	/// maximum line width of `table`, no limit when `None`
+	pub width: Option<usize>,
+}
+
+impl Output {
+	pub fn new(arg: &CommandLine) -> Self {
+		Self {
+			format: arg.format,
+			header: !arg.no_header,
+			width: terminal_width(),
+		}
+	}
+
+	/// Print records, `columns` select fields shown by tabular formats in given order
+	pub fn print<T: Serialize, S: AsRef<str>>(&self, columns: &[S], records: &[T]) -> Result<()> {
+		let mut out = BufWriter::new(stdout().lock());
+		match self.format {
+			OutputFormat::Json => {
+				serde_json::to_writer_pretty(&mut out, records)?;
+				writeln!(out)?;
+			}
+			OutputFormat::Ndjson => {
+				for record in records {
+					serde_json::to_writer(&mut out, record)?;
+					writeln!(out)?;
+				}
+			}
+			format => {
+				let mut rows = Vec::with_capacity(records.len());
+				for record in records {
+					let value = serde_json::to_value(record)?;
+					rows.push(columns.iter().map(|it| match &value[it.as_ref()] {
+						// empty field is null in csv
+						Value::Null if format == OutputFormat::Csv => String::new(),
+						value => cell(value),
+					}).collect::<Vec<_>>());
+				}
+				let header = columns.iter().map(|it| it.as_ref().to_string()).collect::<Vec<_>>();
+				match format {
+					OutputFormat::Csv => self.write_csv(&mut out, &header, &rows)?,
+					OutputFormat::Plain => self.write_plain(&mut out, &header, &rows)?,
+					_ => self.write_table(&mut out, &header, &rows)?,
+				}
+			}
+		}
+		out.flush()?;
+		Ok(())
+	}
+
+	fn write_csv(&self, out: &mut impl Write, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+		let header = self.header.then_some(header);
+		for row in header.into_iter().chain(rows.iter().map(Vec::as_slice)) {
+			let line = row.iter().map(|it| csv_escape(it)).collect::<Vec<_>>().join(",");
+			writeln!(out, "{line}")?;
+		}
+		Ok(())
+	}
+
+	/// Tab separated values without alignment, for `cut` and `awk`
+	fn write_plain(&self, out: &mut impl Write, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+		let header = self.header.then_some(header);
+		for row in header.into_iter().chain(rows.iter().map(Vec::as_slice)) {
+			let line = row.iter().map(|it| it.replace(['\t', '\n'], " ")).collect::<Vec<_>>().join("\t");
+			writeln!(out, "{line}")?;
+		}
+		Ok(())
+	}
+
+	/// Columns aligned to the widest cell, the widest columns are truncated until the line fits [Output::width]
+	fn write_table(&self, out: &mut impl Write, header: &[String], rows: &[Vec<String>]) -> Result<()> {
+		let header = self.header.then_some(header);
+		let lines = header.into_iter().chain(rows.iter().map(Vec::as_slice))
+			.map(|row| row.iter().map(|it| it.replace('\n', " ")).collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		let mut widths = vec![0; header.map(<[_]>::len).unwrap_or_else(|| rows.first().map(Vec::len).unwrap_or_default())];
+		for line in &lines {
+			for (w, col) in widths.iter_mut().zip(line) {
+				*w = (*w).max(col.chars().count());
+			}
+		}
+		if let Some(width) = self.width {
+			fit(&mut widths, width);
+		}
+		for line in lines {
+			let line = line.iter().zip(&widths)
+				.map(|(col, &w)| format!("{:w$}", truncate(col, w)))
+				.collect::<Vec<_>>()
+				.join("  ");
+			writeln!(out, "{}", line.trim_end())?;
+		}
+		Ok(())
+	}
+}
+
+/// Shrink the widest column one character at a time, columns never go below 4 characters
+fn fit(widths: &mut [usize], width: usize) {
+	let separators = widths.len().saturating_sub(1) * 2;
+	while widths.iter().sum::<usize>() + separators > width {
+		match widths.iter_mut().max() {
+			Some(w) if *w > 4 => *w -= 1,
+			_ => break,
+		}
+	}
+}
+
+fn truncate(s: &str, width: usize) -> String {
+	if s.chars().count() <= width {
+		return s.to_string();
+	}
+	let mut s = s.chars().take(width.saturating_sub(1)).collect::<String>();
+	s.push('…');
+	s
+}
+
+fn csv_escape(s: &str) -> String {
+	if s.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", s.replace('"', "\"\""))
+	} else {
+		s.to_string()
+	}
+}
+
+/// `$COLUMNS` if set, otherwise size of terminal attached to stdout
+fn terminal_width() -> Option<usize> {
+	if let Some(columns) = env::var("COLUMNS").ok().and_then(|it| it.parse().ok()) {
+		return Some(columns);
+	}
+	// SAFETY: winsize is plain data and only read after ioctl succeeded
+	unsafe {
+		if libc::isatty(libc::STDOUT_FILENO) == 0 {
+			return None;
+		}
+		let mut size = std::mem::zeroed::<libc::winsize>();
+		if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+			Some(size.ws_col as usize)
+		} else {
+			None
+		}
+	}
+}
+
+/// Display json value inside table cell
+pub fn cell(value: &Value) -> String {
+	match value {
+		Value::Null => String::from("-"),
+		Value::String(s) => s.clone(),
+		value => value.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{csv_escape, fit, truncate};
+
+	#[test]
+	fn fit_to_width() {
+		let mut widths = [10, 40, 6];
+		fit(&mut widths, 40);
+		assert_eq!(widths, [10, 20, 6]);
+		assert_eq!(truncate("description", 5), "desc…");
+		assert_eq!(truncate("name", 5), "name");
+
+		// can't shrink below 4 characters
+		let mut widths = [5, 5];
+		fit(&mut widths, 4);
+		assert_eq!(widths, [4, 4]);
+	}
+
+	#[test]
+	fn csv_quoting() {
+		assert_eq!(csv_escape("glibc"), "glibc");
+		assert_eq!(csv_escape("a,b"), "\"a,b\"");
+		assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+	}
+}
@@ -0,0 +1,57 @@
+use anyhow::{bail, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::db::{DbHandler, Repository};
+use crate::wrapper::info::InstalledPackage;
+use crate::wrapper::repo::Package;
+
+pub const TABLES: [&str; 2] = ["Packages", "InstalledPackages"];
+
+#[derive(Debug, Default)]
+pub struct QueryOptions {
+	/// columns to keep, every column when empty
+	pub fields: Vec<String>,
+	/// columns to sort by, prefix with `-` for descending order
+	pub sort: Vec<String>,
+	pub limit: Option<usize>,
+}
+
+/// Result of [query], `columns` are in table order unless selected by [QueryOptions::fields] or `$fields`
+pub struct QueryResult {
+	pub columns: Vec<String>,
+	pub rows: Vec<Map<String, Value>>,
+}
+
+/// Run json filter against table by name (case-insensitive)
+pub fn query(db: &DbHandler, table: &str, filter: Value, options: &QueryOptions) -> Result<QueryResult> {
+	match table.to_lowercase().as_str() {
+		"packages" => query_repository(&db.get_repository::<Package>()?, filter, options),
+		"installedpackages" => query_repository(&db.get_repository::<InstalledPackage>()?, filter, options),
+		_ => bail!("Unknown table `{}`, available tables: {}", table, TABLES.join(", ")),
+	}
+}
+
+fn query_repository<T: Serialize + DeserializeOwned>(repo: &Repository<T>, filter: Value, options: &QueryOptions) -> Result<QueryResult> {
+	let columns = repo.columns();
+	for field in options.fields.iter().map(String::as_str).chain(options.sort.iter().map(|it| it.strip_prefix('-').unwrap_or(it))) {
+		if !columns.contains(&field) {
+			bail!("Unknown field `{}`, available fields: {}", field, columns.join(", "));
+		}
+	}
+
+	// options are applied after `$sort`, `$limit` and `$fields` of the filter
+	let mut query = repo.query().filter(filter);
+	for column in &options.sort {
+		query = query.order_by(column);
+	}
+	if let Some(limit) = options.limit {
+		query = query.limit(limit);
+	}
+	if !options.fields.is_empty() {
+		query = query.select(&options.fields.iter().map(String::as_str).collect::<Vec<_>>());
+	}
+	let columns = query.columns().into_iter().map(String::from).collect();
+	Ok(QueryResult { columns, rows: query.values()? })
+}
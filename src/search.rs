@@ -0,0 +1,64 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::db::DbHandler;
+use crate::version::PkgVersion;
+use crate::wrapper::repo::Package;
+
+/// Columns printed by tabular formats
+pub const COLUMNS: [&str; 5] = ["repo", "name", "version", "installed", "snippet"];
+
+#[derive(Serialize, Debug)]
+pub struct SearchResult {
+	pub repo: String,
+	pub name: String,
+	pub version: PkgVersion,
+	pub installed: Option<PkgVersion>,
+	/// `bm25` of the package, lower is better
+	pub rank: f64,
+	/// matched terms are enclosed in `[` and `]`
+	pub snippet: String,
+}
+
+/// Packages matching every term and `filter`, best match first
+pub fn search(db: &DbHandler, terms: &[String], filter: Value, limit: Option<usize>) -> Result<Vec<SearchResult>> {
+	let query = fts_query(terms);
+	if query.is_empty() {
+		bail!("Search terms are empty");
+	}
+	let hits = db.get_repository::<Package>()?.search(&query, filter, limit)?;
+	Ok(hits.into_iter()
+		.map(|it| SearchResult {
+			repo: it.row.repo,
+			name: it.row.name,
+			version: it.row.version,
+			installed: it.row.installed,
+			rank: it.rank,
+			snippet: it.snippet,
+		})
+		.collect())
+}
+
+/// Quote every term so characters like `-` or `+` aren't fts5 operators, trailing `*` is kept as prefix query
+fn fts_query(terms: &[String]) -> String {
+	terms.iter()
+		.filter(|it| !it.trim_end_matches('*').is_empty())
+		.map(|it| match it.strip_suffix('*') {
+			Some(prefix) => format!("\"{}\"*", prefix.replace('"', "\"\"")),
+			None => format!("\"{}\"", it.replace('"', "\"\"")),
+		})
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::fts_query;
+
+	#[test]
+	fn quote_terms() {
+		let terms = ["pdf*", "c++", "say \"hi\"", "*"].map(String::from);
+		assert_eq!(fts_query(&terms), r#""pdf"* "c++" "say ""hi""""#);
+	}
+}
@@ -1,13 +1,8 @@
-use std::collections::HashMap;
 use std::process::exit;
 
 use serde_json::Value;
 use tracing::error;
 
-pub use json_manipulator::*;
-
-mod json_manipulator;
-
 pub fn parse_json(input: &str) -> Value {
 	if let Ok(value) = serde_json::from_str::<Value>(input) {
 		return value;
@@ -16,25 +11,17 @@ pub fn parse_json(input: &str) -> Value {
 	exit(1);
 }
 
-pub fn split<T>(arr: Vec<T>, mut filter: impl FnMut(&T) -> bool) -> (Vec<T>, Vec<T>) {
-	let mut left = Vec::new();
-	let mut right = Vec::new();
-	for x in arr.into_iter() {
-		if filter(&x) {
-			left.push(x);
-		} else {
-			right.push(x);
-		}
+pub fn human_size(bytes: i64) -> String {
+	const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
 	}
-	(left, right)
-}
-
-
-pub fn group<T>(arr: Vec<T>, mut by: impl FnMut(&T) -> String) -> HashMap<String, Vec<T>> {
-	let mut map: HashMap<String, Vec<T>> = HashMap::new();
-	for x in arr {
-		let key = by(&x);
-		map.entry(key).or_default().push(x);
+	if unit == 0 {
+		format!("{} {}", bytes, UNITS[0])
+	} else {
+		format!("{:.2} {}", size, UNITS[unit])
 	}
-	map
 }
\ No newline at end of file
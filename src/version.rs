@@ -0,0 +1,280 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use rusqlite::Connection;
+use rusqlite::functions::FunctionFlags;
+use serde::{Deserialize, Serialize};
+
+/// Package version in pacman format `[epoch:]pkgver[-pkgrel]`,
+/// ordered the same way as `alpm_pkg_vercmp`.
+///
+/// Stored as `TEXT COLLATE PkgVersion` so comparison inside sqlite use the same ordering,
+/// see [register].
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PkgVersion(String);
+
+impl PkgVersion {
+	pub fn new(version: impl Into<String>) -> Self {
+		Self(version.into())
+	}
+}
+
+impl From<&str> for PkgVersion {
+	fn from(version: &str) -> Self {
+		Self::new(version)
+	}
+}
+
+impl From<String> for PkgVersion {
+	fn from(version: String) -> Self {
+		Self(version)
+	}
+}
+
+impl Display for PkgVersion {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl PartialEq for PkgVersion {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for PkgVersion {}
+
+impl PartialOrd for PkgVersion {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for PkgVersion {
+	fn cmp(&self, other: &Self) -> Ordering {
+		vercmp(&self.0, &other.0)
+	}
+}
+
+/// Register `PkgVersion` collation and `vercmp(a, b)` function to the connection
+pub fn register(connection: &Connection) -> rusqlite::Result<()> {
+	connection.create_collation("PkgVersion", vercmp)?;
+	connection.create_scalar_function("vercmp", 2, FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC, |ctx| {
+		let a = ctx.get::<String>(0)?;
+		let b = ctx.get::<String>(1)?;
+		Ok(vercmp(&a, &b) as i8)
+	})
+}
+
+/// Split `epoch:version-release`, epoch is `0` when missing
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+	let digits = evr.bytes().take_while(u8::is_ascii_digit).count();
+	let (epoch, rest) = if evr[digits..].starts_with(':') {
+		let epoch = &evr[..digits];
+		(if epoch.is_empty() { "0" } else { epoch }, &evr[digits + 1..])
+	} else {
+		("0", evr)
+	};
+	match rest.rfind('-') {
+		Some(idx) => (epoch, &rest[..idx], Some(&rest[idx + 1..])),
+		None => (epoch, rest, None),
+	}
+}
+
+/// Port of `alpm_pkg_vercmp`
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+	if a == b {
+		return Ordering::Equal;
+	}
+	let (epoch1, ver1, rel1) = parse_evr(a);
+	let (epoch2, ver2, rel2) = parse_evr(b);
+	rpmvercmp(epoch1, epoch2)
+		.then_with(|| rpmvercmp(ver1, ver2))
+		.then_with(|| match (rel1, rel2) {
+			(Some(rel1), Some(rel2)) => rpmvercmp(rel1, rel2),
+			_ => Ordering::Equal,
+		})
+}
+
+/// Port of `rpmvercmp` from libalpm
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+	if a == b {
+		return Ordering::Equal;
+	}
+	let (a, b) = (a.as_bytes(), b.as_bytes());
+	let (mut one, mut two) = (0, 0);
+
+	while one < a.len() && two < b.len() {
+		let (start1, start2) = (one, two);
+		while one < a.len() && !a[one].is_ascii_alphanumeric() { one += 1; }
+		while two < b.len() && !b[two].is_ascii_alphanumeric() { two += 1; }
+
+		// ran to the end of either
+		if one >= a.len() || two >= b.len() {
+			break;
+		}
+
+		// separator lengths are different
+		if one - start1 != two - start2 {
+			return (one - start1).cmp(&(two - start2));
+		}
+
+		let is_num = a[one].is_ascii_digit();
+		let class = if is_num { u8::is_ascii_digit } else { u8::is_ascii_alphabetic };
+		let end1 = one + a[one..].iter().take_while(|it| class(it)).count();
+		let end2 = two + b[two..].iter().take_while(|it| class(it)).count();
+
+		// different segment types, numeric is always newer than alpha
+		if two == end2 {
+			return if is_num { Ordering::Greater } else { Ordering::Less };
+		}
+
+		let (mut seg1, mut seg2) = (&a[one..end1], &b[two..end2]);
+		if is_num {
+			while let [b'0', rest @ ..] = seg1 { seg1 = rest; }
+			while let [b'0', rest @ ..] = seg2 { seg2 = rest; }
+			// whichever number has more digits wins
+			match seg1.len().cmp(&seg2.len()) {
+				Ordering::Equal => {}
+				ord => return ord,
+			}
+		}
+		match seg1.cmp(seg2) {
+			Ordering::Equal => {}
+			ord => return ord,
+		}
+
+		one = end1;
+		two = end2;
+	}
+
+	let (rest1, rest2) = (a.get(one), b.get(two));
+	match (rest1, rest2) {
+		// segments are identical but separators are different
+		(None, None) => Ordering::Equal,
+		// remaining alpha string never beat empty string
+		(None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+		(Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+		_ => Ordering::Greater,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cmp::Ordering;
+
+	use super::{PkgVersion, vercmp};
+
+	/// Test cases from pacman `test/util/vercmptest.sh`
+	const CASES: &[(&str, &str, i8)] = &[
+		// all similar length, no pkgrel
+		("1.5.0", "1.5.0", 0),
+		("1.5.1", "1.5.0", 1),
+		// mixed length
+		("1.5.1", "1.5", 1),
+		// with pkgrel, simple
+		("1.5.0-1", "1.5.0-1", 0),
+		("1.5.0-1", "1.5.0-2", -1),
+		("1.5.0-1", "1.5.1-1", -1),
+		("1.5.0-2", "1.5.1-1", -1),
+		// with pkgrel, mixed lengths
+		("1.5-1", "1.5.1-1", -1),
+		("1.5-2", "1.5.1-1", -1),
+		("1.5-2", "1.5.1-2", -1),
+		// mixed pkgrel inclusion
+		("1.5", "1.5-1", 0),
+		("1.5-1", "1.5", 0),
+		("1.1-1", "1.1", 0),
+		("1.0-1", "1.1", -1),
+		("1.1-1", "1.0", 1),
+		// alphanumeric versions
+		("1.5b-1", "1.5-1", -1),
+		("1.5b", "1.5", -1),
+		("1.5b-1", "1.5", -1),
+		("1.5b", "1.5.1", -1),
+		// from the manpage
+		("1.0a", "1.0alpha", -1),
+		("1.0alpha", "1.0b", -1),
+		("1.0b", "1.0beta", -1),
+		("1.0beta", "1.0rc", -1),
+		("1.0rc", "1.0", -1),
+		// going crazy? alpha-dotted versions
+		("1.5.a", "1.5", 1),
+		("1.5.b", "1.5.a", 1),
+		("1.5.1", "1.5.b", 1),
+		// alpha dots and dashes
+		("1.5.b-1", "1.5.b", 0),
+		("1.5-1", "1.5.b", -1),
+		// same/similar content, differing separators
+		("2.0", "2_0", 0),
+		("2.0_a", "2_0.a", 0),
+		("2.0a", "2.0.a", -1),
+		("2___a", "2_a", 1),
+		// epoch included version comparisons
+		("0:1.0", "0:1.0", 0),
+		("0:1.0", "0:1.1", -1),
+		("1:1.0", "0:1.0", 1),
+		("1:1.0", "0:1.1", 1),
+		("1:1.0", "2:1.1", -1),
+		// epoch + sometimes present pkgrel
+		("1:1.0", "0:1.0-1", 1),
+		("1:1.0-1", "0:1.1-1", 1),
+		// epoch included on one version
+		("0:1.0", "1.0", 0),
+		("0:1.0", "1.1", -1),
+		("0:1.1", "1.0", 1),
+		("1:1.0", "1.0", 1),
+		("1:1.0", "1.1", 1),
+		("1:1.1", "1.1", 1),
+	];
+
+	#[test]
+	fn pacman_vercmp_cases() {
+		for &(a, b, expected) in CASES {
+			let expected = expected.cmp(&0);
+			assert_eq!(vercmp(a, b), expected, "vercmp({a}, {b})");
+			assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({b}, {a})");
+		}
+	}
+
+	#[test]
+	fn sqlite_collation() {
+		let connection = rusqlite::Connection::open_in_memory().unwrap();
+		super::register(&connection).unwrap();
+		connection.execute("CREATE TABLE v (version TEXT COLLATE PkgVersion)", []).unwrap();
+		for version in ["1:2.0-1", "1.10-1", "1.9-1", "2.0-1"] {
+			connection.execute("INSERT INTO v VALUES (?)", [version]).unwrap();
+		}
+		let mut stmt = connection.prepare("SELECT version FROM v WHERE version > ? ORDER BY version").unwrap();
+		let found = stmt.query_map(["1.9-1"], |row| row.get::<_, String>(0)).unwrap()
+			.collect::<rusqlite::Result<Vec<_>>>().unwrap();
+		assert_eq!(found, ["1.10-1", "2.0-1", "1:2.0-1"]);
+
+		let ord: i8 = connection.query_row("SELECT vercmp('1.0rc', '1.0')", [], |row| row.get(0)).unwrap();
+		assert_eq!(ord, -1);
+		assert_eq!(PkgVersion::from("1:1.0").cmp(&PkgVersion::from("2.0")), Ordering::Greater);
+	}
+
+	#[test]
+	fn repository_find_by_version() {
+		use serde_json::json;
+
+		use crate::db::db_init;
+		use crate::wrapper::repo::Package;
+
+		let db = db_init(None).unwrap();
+		let repo = db.get_repository::<Package>().unwrap();
+		for version in ["1.9-1", "1.10-1", "1:2.0-1", "1:2.1-1"] {
+			// (repo, name) is unique
+			repo.add(Package { repo: String::from("core"), name: format!("foo{version}"), version: PkgVersion::from(version), installed: None, ..Default::default() }).unwrap();
+		}
+		let found = repo.find(json!({"version":{"$gt":"1:2.0-1"}})).unwrap();
+		assert_eq!(found.iter().map(|it| it.version.to_string()).collect::<Vec<_>>(), ["1:2.1-1"]);
+		let found = repo.find(json!({"version":{"$gte":"1.10","$lt":"1:0"}})).unwrap();
+		assert_eq!(found.iter().map(|it| it.version.to_string()).collect::<Vec<_>>(), ["1.10-1"]);
+		// sorted with collation of the column
+		let found = repo.query().order_by("-version").limit(3).all().unwrap();
+		assert_eq!(found.iter().map(|it| it.version.to_string()).collect::<Vec<_>>(), ["1:2.1-1", "1:2.0-1", "1.10-1"]);
+	}
+}
@@ -5,6 +5,8 @@ use anyhow::Result;
 pub mod repo;
 pub mod info;
 pub mod install;
+pub mod desc;
+pub mod plan;
 
 pub trait PacmanArg<'a> {
 	fn to_args(&'a self) -> Vec<&'a str>;
@@ -28,19 +30,13 @@ impl<'a> PacmanArg<'a> for Vec<String> {
 	}
 }
 
+/// Spawn pacman for a call that changes the system, its progress and errors go to the terminal.
+/// Pipe stdout of your own [Command] instead when the output needs to be parsed.
 pub fn pacman<'a>(args: &'a impl PacmanArg<'a>) -> Result<Child> {
 	Ok(Command::new("pacman")
 		.args(args.to_args())
-		.stdout(Stdio::piped())
+		.stdout(Stdio::inherit())
 		.stdin(Stdio::null())
-		.stderr(Stdio::null())
+		.stderr(Stdio::inherit())
 		.spawn()?)
 }
-/*
-pub fn list_installed_with_detail() -> Vec<Array<Value>> {
-	let mut right = list_all().unwrap().into_iter().map(|it| it.cvt_object()).collect::<Vec<_>>();
-	let left = list_installed().unwrap();
-	right_join(&left, &mut right, "name");
-	let packages = right.into_iter().filter(|it| obj_match(&json!({"installed":"*"}), it)).collect();
-	group(packages, "name")
-}*/
\ No newline at end of file
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+/// Sections of `desc` file from pacman database,
+/// each section start with `%KEY%` line followed by values until blank line.
+pub struct Desc<'a> {
+	sections: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Desc<'a> {
+	pub fn parse(data: &'a str) -> Self {
+		let mut sections: HashMap<&str, Vec<&str>> = HashMap::new();
+		let mut current = None;
+		for line in data.lines() {
+			if line.is_empty() {
+				current = None;
+			} else if let Some(key) = current {
+				sections.entry(key).or_default().push(line);
+			} else if let Some(key) = line.strip_prefix('%').and_then(|it| it.strip_suffix('%')) {
+				sections.entry(key).or_default();
+				current = Some(key);
+			}
+		}
+		Self { sections }
+	}
+
+	pub fn string(&self, key: &str) -> String {
+		self.sections.get(key)
+			.map(|it| it.join("\n"))
+			.unwrap_or_default()
+	}
+
+	pub fn number(&self, key: &str) -> i64 {
+		self.sections.get(key)
+			.and_then(|it| it.first())
+			.and_then(|it| it.parse().ok())
+			.unwrap_or_default()
+	}
+
+	pub fn list(&self, key: &str) -> Vec<String> {
+		self.sections.get(key)
+			.map(|it| it.iter().map(|it| it.to_string()).collect())
+			.unwrap_or_default()
+	}
+}
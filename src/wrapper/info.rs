@@ -1,82 +1,127 @@
-use std::collections::HashMap;
-use std::io::Read;
-use std::process::{Command, Stdio};
+use std::fs;
+use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::db::Repository;
-
-#[derive(Debug)]
-pub struct PackageInfo<'a> {
-	pub name: &'a str,
-	pub installed: &'a str,
-	pub architecture: &'a str,
-	pub url: &'a str,
-	pub as_dependency: bool,
-}
+use crate::db::{DbResult, Migration, Repository, TableOptions};
+use crate::wrapper::desc::Desc;
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct InstalledPackage {
 	pub name: String,
 	pub installed: String,
+	pub base: String,
+	pub description: String,
 	pub architecture: String,
 	pub url: String,
 	pub packager: String,
-	pub as_dependency: i8, // bool but unsupported by serde_json
+	pub build_date: i64,
+	pub install_date: i64,
+	pub size: i64,
+	pub validation: String,
+	pub as_dependency: bool,
+	pub licenses: Vec<String>,
+	pub groups: Vec<String>,
+	pub depends: Vec<String>,
+	pub optdepends: Vec<String>,
+	pub provides: Vec<String>,
+	pub conflicts: Vec<String>,
+	pub replaces: Vec<String>,
 }
 
-// impl<'a> PackageInfo<'a> {
-// 	pub fn to_object(self) -> Value {
-// 		//	"packager": self.packager.to_string(),
-// 		json!({
-// 			"name": self.name.to_string(),
-// 			"installed": self.installed.to_string(),
-// 			"architecture": self.architecture.to_string(),
-// 			"url": self.url.to_string(),
-// 			"as_dependency": self.as_dependency
-// 		})
-// 	}
-// }
+impl TableOptions for InstalledPackage {
+	fn primary_key() -> &'static [&'static str] {
+		&["name"]
+	}
 
-pub fn list_installed(repo: &Repository<InstalledPackage>) -> Result<u64> {
-	info!("Executing `pacman -Qi`");
-	let mut cmd = Command::new("pacman")
-		.arg("-Qi")
-		.stdout(Stdio::piped())
-		.stdin(Stdio::null())
-		.stderr(Stdio::null())
-		.spawn()?;
-	let mut data = String::new();
-	cmd.stdout.take().unwrap().read_to_string(&mut data)?;
-	Ok(parse(&data, repo))
+	fn collations() -> &'static [(&'static str, &'static str)] {
+		&[("installed", "PkgVersion")]
+	}
+
+	fn migration() -> Migration {
+		Migration::Reset
+	}
 }
 
-fn parse(str: &str, repo: &Repository<InstalledPackage>) -> u64 {
-	let mut packages_count = 0;
-	let packages = str.split("\n\n");
-	for package in packages {
-		let lines = package.split('\n');
-		let mut map = HashMap::new();
-		for line in lines {
-			let mut l = line.splitn(2, ':');
-			if let (Some(k), Some(v)) = (l.next(), l.next()) {
-				map.insert(k.trim(), v.trim());
-			}
+impl InstalledPackage {
+	pub fn from_desc(desc: &Desc) -> Self {
+		Self {
+			name: desc.string("NAME"),
+			installed: desc.string("VERSION"),
+			base: desc.string("BASE"),
+			description: desc.string("DESC"),
+			architecture: desc.string("ARCH"),
+			url: desc.string("URL"),
+			packager: desc.string("PACKAGER"),
+			build_date: desc.number("BUILDDATE"),
+			install_date: desc.number("INSTALLDATE"),
+			size: desc.number("SIZE"),
+			validation: desc.list("VALIDATION").join(" "),
+			// explicitly installed package don't have %REASON%
+			as_dependency: desc.number("REASON") == 1,
+			licenses: desc.list("LICENSE"),
+			groups: desc.list("GROUPS"),
+			depends: desc.list("DEPENDS"),
+			optdepends: desc.list("OPTDEPENDS"),
+			provides: desc.list("PROVIDES"),
+			conflicts: desc.list("CONFLICTS"),
+			replaces: desc.list("REPLACES"),
 		}
-		if let Some(name) = map.get("Name") {
-			repo.add(InstalledPackage {
-				name: name.to_string(),
-				installed: map.get("Version").unwrap().to_string(),
-				architecture: map.get("Architecture").unwrap().to_string(),
-				url: map.get("URL").unwrap().to_string(),
-				packager: map.get("Packager").unwrap().to_string(),
-				as_dependency: map.get("Install Reason").map(|it| it.contains("as a dependency")).unwrap_or_default() as i8,
-			});
-			packages_count += 1;
+	}
+}
+
+/// Read installed packages from `<dbpath>/local/*/desc`
+pub fn read_local_db(dbpath: &Path) -> Result<Vec<InstalledPackage>> {
+	let local = dbpath.join("local");
+	info!("Reading local database from `{}`", local.display());
+	let mut packages = Vec::new();
+	for entry in fs::read_dir(&local).with_context(|| format!("Failed to read `{}`", local.display()))? {
+		let path = entry?.path().join("desc");
+		// skip ALPM_DB_VERSION
+		if !path.is_file() {
+			continue;
 		}
+		let data = fs::read_to_string(&path)?;
+		let package = InstalledPackage::from_desc(&Desc::parse(&data));
+		if package.name.is_empty() {
+			warn!("Missing package name in `{}`", path.display());
+			continue;
+		}
+		packages.push(package);
 	}
+	Ok(packages)
+}
+
+pub fn list_installed(repo: &Repository<InstalledPackage>, packages: Vec<InstalledPackage>) -> DbResult<u64> {
+	let packages_count = repo.insert_all(packages)? as u64;
 	info!("Found {} installed package", packages_count);
-	packages_count
-}
\ No newline at end of file
+	Ok(packages_count)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::read_local_db;
+
+	#[test]
+	fn read_fixture_local_db() {
+		let dbpath = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/db");
+		let mut packages = read_local_db(&dbpath).unwrap();
+		packages.sort_by(|a, b| a.name.cmp(&b.name));
+		assert_eq!(packages.iter().map(|it| it.name.as_str()).collect::<Vec<_>>(), ["glibc", "pacman", "perl-locale-gettext"]);
+
+		let pacman = &packages[1];
+		assert_eq!(pacman.installed, "6.0.2-6");
+		assert!(!pacman.as_dependency);
+		assert_eq!(pacman.install_date, 1666000000);
+		assert_eq!(pacman.validation, "pgp");
+		assert_eq!(pacman.depends, ["bash", "glibc", "libarchive"]);
+		assert_eq!(pacman.optdepends, ["perl-locale-gettext: translation support in makepkg-template"]);
+		assert_eq!(pacman.licenses, ["GPL"]);
+		assert!(packages[0].as_dependency);
+		assert_eq!(packages[0].provides, ["glibc-locales"]);
+	}
+}
@@ -1,72 +1,109 @@
 use anyhow::{bail, Result};
-use serde::Deserialize;
-use tracing::{error, info};
+use serde_json::json;
+use tracing::{debug, error, info};
 
+use crate::db::{DbHandler, DbResult};
+use crate::wrapper::info::InstalledPackage;
 use crate::wrapper::pacman;
+use crate::wrapper::plan::{Action, Plan, PlanEntry};
+use crate::wrapper::repo::Package;
 
-#[derive(Deserialize, Debug)]
-pub struct InstallablePackage {
-	repo: String,
-	name: String,
-	as_dependency: bool,
-}
+/// Run every entry of the plan using pacman,
+/// entries are marked as done in `db` after each successful pacman call.
+pub fn execute(plan: &Plan, db: Option<&DbHandler>) -> Result<()> {
+	let installs = plan.entries.iter()
+		.filter(|it| it.action == Action::Replace);
+	let explicit = installs.clone()
+		.filter(|it| !it.as_dependency)
+		.collect::<Vec<_>>();
+	let deps = installs
+		.filter(|it| it.as_dependency)
+		.collect::<Vec<_>>();
 
-pub fn install_pkg(pkg: &InstallablePackage) -> Result<()> {
-	let mut cmd = Vec::new();
-	let mut pkg_name = String::new();
-	cmd.push(String::from("-S"));
-	cmd.push(String::from("--noconfirm"));
-	pkg_name.push_str(&pkg.repo);
-	pkg_name.push('/');
-	pkg_name.push_str(&pkg.name);
-	cmd.push(pkg_name);
-	if pkg.as_dependency {
-		cmd.push(String::from("--asdeps"));
-	}
-	info!("Installing {}/{}",pkg.repo,pkg.name);
-	let mut child = pacman(&cmd)?;
-	child.wait()?;
+	install_pkgs(targets(&explicit), false)?;
+	mark_done(db, &explicit)?;
+	install_pkgs(targets(&deps), true)?;
+	mark_done(db, &deps)?;
+
+	let removes = plan.entries.iter()
+		.filter(|it| it.action == Action::Remove)
+		.collect::<Vec<_>>();
+	remove_pkgs(targets(&removes))?;
+	mark_done(db, &removes)?;
 	Ok(())
 }
 
-pub fn install_pkgs(pkgs: &[impl AsRef<InstallablePackage>]) -> Result<()> {
-	let mut deps = pkgs.iter()
-		.map(|it| it.as_ref())
-		.filter(|it| it.as_dependency)
-		.map(|p| format!("{}/{}", p.repo, p.name))
-		.collect::<Vec<_>>();
-	let mut explicit = pkgs.iter()
-		.map(|it| it.as_ref())
-		.filter(|it| !it.as_dependency)
-		.map(|p| format!("{}/{}", p.repo, p.name))
-		.collect::<Vec<_>>();
+fn targets(entries: &[&PlanEntry]) -> Vec<String> {
+	entries.iter().map(|it| it.target()).collect()
+}
 
-	let (deps_len, exp_len) = (deps.len(), explicit.len());
+/// Update installed version in package tables, so the database matches pacman before the cache is rebuilt
+fn mark_done(db: Option<&DbHandler>, entries: &[&PlanEntry]) -> DbResult<()> {
+	let Some(db) = db else {
+		return Ok(());
+	};
+	let packages = db.get_repository::<Package>()?;
+	let installed = db.get_repository::<InstalledPackage>()?;
+	for entry in entries {
+		// `$eq` so `*` or `?` in name isn't treated as wildcard
+		let name = json!({"$eq": entry.name});
+		match entry.action {
+			Action::Replace => {
+				packages.update(json!({"name": name}), json!({"installed": null}))?;
+				packages.update(json!({"repo": {"$eq": entry.to_repo}, "name": name}), json!({"installed": entry.to_version}))?;
+				installed.update(json!({"name": name}), json!({"installed": entry.to_version}))?;
+			}
+			Action::Remove => {
+				packages.update(json!({"name": name}), json!({"installed": null}))?;
+				installed.delete(json!({"name": name}))?;
+			}
+			Action::Skip => {}
+		}
+	}
+	debug!("Marked {} entries as done", entries.len());
+	Ok(())
+}
 
-	// TODO: redirect stdout to file
-	let mut exps_cmd = Vec::new();
-	exps_cmd.push(String::from("-S"));
-	exps_cmd.push(String::from("--noconfirm"));
-	exps_cmd.extend(explicit);
+fn remove_pkgs(targets: Vec<String>) -> Result<()> {
+	if targets.is_empty() {
+		return Ok(());
+	}
+	let mut cmd = Vec::new();
+	cmd.push(String::from("-Rns"));
+	cmd.push(String::from("--noconfirm"));
+	info!("Removing {} packages", targets.len());
+	cmd.extend(targets);
 
-	info!("Installing {} packages",exp_len);
-	let mut child = pacman(&exps_cmd)?;
+	let mut child = pacman(&cmd)?;
 	if !child.wait()?.success() {
 		error!("Error while executing pacman");
 		bail!("Failed to execute pacman")
 	};
+	Ok(())
+}
 
-	let mut deps_cmd = Vec::new();
-	deps_cmd.push(String::from("-S"));
-	deps_cmd.push(String::from("--noconfirm"));
-	deps_cmd.extend(deps);
-	deps_cmd.push(String::from("--asdeps"));
+fn install_pkgs(targets: Vec<String>, as_dependency: bool) -> Result<()> {
+	if targets.is_empty() {
+		return Ok(());
+	}
+	let len = targets.len();
 
-	info!("Installing {} packages as dependency",deps_len);
-	let mut child = pacman(&deps_cmd)?;
+	// TODO: redirect stdout to file
+	let mut cmd = Vec::new();
+	cmd.push(String::from("-S"));
+	cmd.push(String::from("--noconfirm"));
+	cmd.extend(targets);
+	if as_dependency {
+		cmd.push(String::from("--asdeps"));
+		info!("Installing {} packages as dependency", len);
+	} else {
+		info!("Installing {} packages", len);
+	}
+
+	let mut child = pacman(&cmd)?;
 	if !child.wait()?.success() {
 		error!("Error while executing pacman");
 		bail!("Failed to execute pacman")
 	};
 	Ok(())
-}
\ No newline at end of file
+}
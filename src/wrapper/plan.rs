@@ -0,0 +1,107 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::cli::OutputFormat;
+use crate::output::Output;
+use crate::util::human_size;
+use crate::version::PkgVersion;
+
+#[derive(Serialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+	Replace,
+	Remove,
+	/// reported only, e.g. package without alternative, pacman isn't run for it
+	Skip,
+}
+
+impl Action {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Action::Replace => "replace",
+			Action::Remove => "remove",
+			Action::Skip => "skip",
+		}
+	}
+}
+
+#[derive(Serialize, Debug)]
+pub struct PlanEntry {
+	pub name: String,
+	pub from_repo: Option<String>,
+	pub from_version: Option<PkgVersion>,
+	pub to_repo: Option<String>,
+	pub to_version: Option<PkgVersion>,
+	pub as_dependency: bool,
+	/// installed size in bytes after the action, size of removed or skipped package for [Action::Remove] and [Action::Skip]
+	pub size: i64,
+	pub action: Action,
+}
+
+impl PlanEntry {
+	/// `repo/name` target for pacman
+	pub fn target(&self) -> String {
+		match &self.to_repo {
+			Some(repo) => format!("{}/{}", repo, self.name),
+			None => self.name.clone(),
+		}
+	}
+}
+
+/// List of operations to be done by [crate::wrapper::install::execute],
+/// print it first to preview what is going to happen.
+#[derive(Serialize, Debug, Default)]
+#[serde(transparent)]
+pub struct Plan {
+	pub entries: Vec<PlanEntry>,
+}
+
+impl Plan {
+	pub fn push(&mut self, entry: PlanEntry) {
+		self.entries.push(entry);
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn has(&self, action: Action) -> bool {
+		self.entries.iter().any(|it| it.action == action)
+	}
+
+	/// Table shows combined `repo/version` and human readable size, other formats print entries as is
+	pub fn print(&self, output: &Output) -> Result<()> {
+		if output.format != OutputFormat::Table {
+			let columns = ["name", "from_repo", "from_version", "to_repo", "to_version", "as_dependency", "size", "action"];
+			return output.print(&columns, &self.entries);
+		}
+		let version = |repo: &Option<String>, version: &Option<PkgVersion>| match (repo, version) {
+			(Some(repo), Some(version)) => format!("{repo}/{version}"),
+			(None, Some(version)) => version.to_string(),
+			_ => String::from("-"),
+		};
+		let rows = self.entries.iter().map(|it| PlanRow {
+			name: &it.name,
+			from: version(&it.from_repo, &it.from_version),
+			to: version(&it.to_repo, &it.to_version),
+			reason: if it.as_dependency { "dependency" } else { "explicit" },
+			size: human_size(it.size),
+			action: it.action.as_str(),
+		}).collect::<Vec<_>>();
+		output.print(&["name", "from", "to", "reason", "size", "action"], &rows)
+	}
+}
+
+#[derive(Serialize)]
+struct PlanRow<'a> {
+	name: &'a str,
+	from: String,
+	to: String,
+	reason: &'static str,
+	size: String,
+	action: &'static str,
+}
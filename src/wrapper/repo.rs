@@ -1,119 +1,223 @@
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::db::Repository;
-use crate::wrapper::pacman;
+use crate::db::{DbResult, Migration, Repository, TableOptions};
+use crate::version::PkgVersion;
+use crate::wrapper::desc::Desc;
+use crate::wrapper::info::InstalledPackage;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Package {
 	pub repo: String,
 	pub name: String,
-	pub version: String,
-	pub installed: Option<String>,
+	pub version: PkgVersion,
+	pub installed: Option<PkgVersion>,
+	pub base: String,
+	pub description: String,
+	pub architecture: String,
+	pub url: String,
+	pub packager: String,
+	pub build_date: i64,
+	pub csize: i64,
+	pub isize: i64,
+	pub sha256sum: String,
+	pub licenses: Vec<String>,
+	pub groups: Vec<String>,
+	pub depends: Vec<String>,
+	pub optdepends: Vec<String>,
+	pub provides: Vec<String>,
+	pub conflicts: Vec<String>,
+	pub replaces: Vec<String>,
 }
 
-impl Default for Package {
-	fn default() -> Self {
-		Self {
-			repo: Default::default(),
-			name: Default::default(),
-			version: Default::default(),
-			installed: Some(Default::default()),
-		}
+impl TableOptions for Package {
+	fn unique() -> &'static [&'static [&'static str]] {
+		&[&["repo", "name"]]
+	}
+
+	fn collations() -> &'static [(&'static str, &'static str)] {
+		&[("version", "PkgVersion"), ("installed", "PkgVersion")]
+	}
+
+	/// alternatives of installed package are looked up by name
+	fn indexes() -> &'static [&'static [&'static str]] {
+		&[&["name"]]
+	}
+
+	/// used by `search`, match of name is the most relevant
+	fn search() -> &'static [(&'static str, f64)] {
+		&[("name", 10.0), ("description", 2.0), ("groups", 1.0), ("provides", 1.0)]
+	}
+
+	fn migration() -> Migration {
+		Migration::Reset
 	}
 }
 
 impl Package {
-	pub fn cvt_object(self) -> Value {
-		let installed = self.installed.map(Value::String).unwrap_or_default();
-		json!({
-			"repo": self.repo,
-			"name": self.name,
-			"version": self.version,
-			"installed": installed,
-		})
+	pub fn from_desc(repo: &str, desc: &Desc) -> Self {
+		Self {
+			repo: repo.to_string(),
+			name: desc.string("NAME"),
+			version: PkgVersion::from(desc.string("VERSION")),
+			installed: None,
+			base: desc.string("BASE"),
+			description: desc.string("DESC"),
+			architecture: desc.string("ARCH"),
+			url: desc.string("URL"),
+			packager: desc.string("PACKAGER"),
+			build_date: desc.number("BUILDDATE"),
+			csize: desc.number("CSIZE"),
+			isize: desc.number("ISIZE"),
+			sha256sum: desc.string("SHA256SUM"),
+			licenses: desc.list("LICENSE"),
+			groups: desc.list("GROUPS"),
+			depends: desc.list("DEPENDS"),
+			optdepends: desc.list("OPTDEPENDS"),
+			provides: desc.list("PROVIDES"),
+			conflicts: desc.list("CONFLICTS"),
+			replaces: desc.list("REPLACES"),
+		}
 	}
 }
 
-/*pub fn list_all() -> Result<Vec<Package>> {
-	info!("Running `pacman -Sl`");
-	let mut cmd = pacman(&"-Sl")?;
-	let mut stdout = BufReader::new(cmd.stdout.take().unwrap());
-	let mut data = Vec::new();
-	let mut buf = String::new();
-	while let Ok(n) = stdout.read_line(&mut buf) {
-		if n == 0 { break; }
-		let mut info = buf.splitn(4, ' ');
-		if let (Some(repo), Some(name), Some(version), installed) = (info.next(), info.next(), info.next(), info.next()) {
-			let version = version.trim().to_string();
-			let installed = installed.and_then(|it| {
-				if it.starts_with("[installed") {
-					if it.as_bytes()[10] == b']' {
-						Some(version.clone())
-					} else {
-						let mut ver = it.splitn(2, ' ');
-						ver.next();
-						let ver = ver.next().unwrap();
-						Some(ver[..ver.len() - 2].to_string())
-					}
-				} else {
-					None
-				}
-			});
-			data.push(Package {
-				repo: repo.to_string(),
-				name: name.to_string(),
-				version,
-				installed,
-			});
+/// Repositories in order of `pacman.conf`, the first one has the highest priority
+pub fn repos_from_config(config: &Path) -> Result<Vec<String>> {
+	let file = File::open(config).with_context(|| format!("Failed to open `{}`", config.display()))?;
+	let mut repos = Vec::new();
+	for line in BufReader::new(file).lines() {
+		let line = line?;
+		if let Some(section) = line.trim().strip_prefix('[').and_then(|it| it.strip_suffix(']')) {
+			if section != "options" {
+				repos.push(section.to_string());
+			}
 		}
+	}
+	Ok(repos)
+}
 
-		buf = String::new();
-	};
-	info!("Found {} available packages", data.len());
-	Ok(data)
+/// Decompress sync database by checking magic bytes, database without compression is plain tar
+fn open_db(path: &Path) -> Result<Box<dyn Read>> {
+	let mut file = File::open(path).with_context(|| format!("Failed to open `{}`", path.display()))?;
+	let mut magic = [0u8; 6];
+	file.read_exact(&mut magic).ok();
+	file.rewind()?;
+	let file = BufReader::new(file);
+	Ok(match magic {
+		[0x1f, 0x8b, ..] => Box::new(flate2::bufread::GzDecoder::new(file)),
+		[0x28, 0xb5, 0x2f, 0xfd, ..] => Box::new(zstd::Decoder::with_buffer(file)?),
+		[0xfd, b'7', b'z', b'X', b'Z', 0x00] => Box::new(xz2::bufread::XzDecoder::new(file)),
+		_ => Box::new(file),
+	})
 }
-*/
-pub fn list_to_db(repository: &Repository<'_, Package>) -> Result<u64> {
-	info!("Running `pacman -Sl`");
-	let mut cmd = pacman(&"-Sl")?;
-	let mut stdout = BufReader::new(cmd.stdout.take().unwrap());
-	let mut packages = 0;
-	let mut buf = String::new();
-	while let Ok(n) = stdout.read_line(&mut buf) {
-		if n == 0 { break; }
-		let mut info = buf.splitn(4, ' ');
-		if let (Some(repo), Some(name), Some(version), installed) = (info.next(), info.next(), info.next(), info.next()) {
-			let version = version.trim().to_string();
-			let installed = installed.and_then(|it| {
-				if it.starts_with("[installed") {
-					if it.as_bytes()[10] == b']' {
-						Some(version.clone())
-					} else {
-						let mut ver = it.splitn(2, ' ');
-						ver.next();
-						let ver = ver.next().unwrap();
-						Some(ver[..ver.len() - 2].to_string())
-					}
-				} else {
-					None
-				}
-			});
-			repository.add(Package {
-				repo: repo.to_string(),
-				name: name.to_string(),
-				version,
-				installed,
-			});
-			packages += 1;
+
+/// Read packages from sync database `<repo>.db`
+pub fn read_sync_db(path: &Path, repo: &str) -> Result<Vec<Package>> {
+	let mut archive = tar::Archive::new(open_db(path)?);
+	let mut packages = Vec::new();
+	for entry in archive.entries()? {
+		let mut entry = entry?;
+		if !entry.path()?.ends_with("desc") {
+			continue;
 		}
+		let mut data = String::new();
+		entry.read_to_string(&mut data)?;
+		let package = Package::from_desc(repo, &Desc::parse(&data));
+		if package.name.is_empty() {
+			warn!("Missing package name in `{}` of {}", entry.path()?.display(), repo);
+			continue;
+		}
+		packages.push(package);
+	}
+	Ok(packages)
+}
 
-		buf = String::new();
+/// Read every sync database in `<dbpath>/sync` in order of `repos`,
+/// if `repos` is empty all database will be read in alphabetical order.
+pub fn read_sync_dbs(dbpath: &Path, repos: &[String]) -> Result<Vec<Package>> {
+	let sync = dbpath.join("sync");
+	let repos = if repos.is_empty() {
+		let mut repos = Vec::new();
+		for entry in fs::read_dir(&sync).with_context(|| format!("Failed to read `{}`", sync.display()))? {
+			let path = entry?.path();
+			if path.extension().map(|it| it == "db").unwrap_or_default() {
+				if let Some(name) = path.file_stem() {
+					repos.push(name.to_string_lossy().to_string());
+				}
+			}
+		}
+		repos.sort();
+		repos
+	} else {
+		repos.to_vec()
 	};
-	info!("Found {} available packages", packages);
+
+	let mut packages = Vec::new();
+	for repo in repos {
+		let path = sync.join(format!("{repo}.db"));
+		if !path.is_file() {
+			warn!("Missing sync database for {}, try `pacman -Sy`", repo);
+			continue;
+		}
+		info!("Reading sync database `{}`", path.display());
+		packages.extend(read_sync_db(&path, &repo)?);
+	}
 	Ok(packages)
-}
\ No newline at end of file
+}
+
+/// Store sync packages with installed version from `local`
+pub fn list_to_db(repository: &Repository<'_, Package>, packages: Vec<Package>, local: &[InstalledPackage]) -> DbResult<u64> {
+	let installed = local.iter()
+		.map(|it| (it.name.as_str(), PkgVersion::from(it.installed.as_str())))
+		.collect::<HashMap<_, _>>();
+	let count = repository.insert_all(packages.into_iter().map(|mut package| {
+		package.installed = installed.get(package.name.as_str()).cloned();
+		package
+	}))? as u64;
+	info!("Found {} available packages", count);
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use crate::version::PkgVersion;
+
+	use super::{read_sync_dbs, repos_from_config};
+
+	#[test]
+	fn read_fixture_config() {
+		let config = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pacman.conf");
+		// `[options]` and commented out `[community-testing]` aren't repositories
+		assert_eq!(repos_from_config(&config).unwrap(), ["testing", "core", "extra", "community"]);
+		assert!(repos_from_config(Path::new("/nonexistent/pacman.conf")).is_err());
+	}
+
+	#[test]
+	fn read_fixture_sync_db() {
+		let dbpath = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/db");
+		// one database for each compression
+		let repos = ["core", "extra", "community", "testing"].map(String::from);
+		let packages = read_sync_dbs(&dbpath, &repos).unwrap();
+		assert_eq!(
+			packages.iter().map(|it| format!("{}/{}", it.repo, it.name)).collect::<Vec<_>>(),
+			["core/glibc", "core/pacman", "extra/poppler", "community/fzf", "testing/glibc"]
+		);
+
+		let pacman = &packages[1];
+		assert_eq!(pacman.version, PkgVersion::from("6.0.2-6"));
+		assert_eq!(pacman.csize, 865000);
+		assert_eq!(pacman.isize, 4743826);
+		assert_eq!(pacman.sha256sum, "a6b1fe1b2d2d4c2ad4a54a1b0b9a3ff5f2a2c79cfc2c3c0a9c0d6a0bf7a9c9c5");
+		assert_eq!(pacman.depends, ["bash", "glibc", "libarchive"]);
+		assert_eq!(pacman.groups, ["base-devel"]);
+		assert_eq!(packages[4].version, PkgVersion::from("2.37-1"));
+	}
+}